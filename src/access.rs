@@ -0,0 +1,170 @@
+use std::iter::FusedIterator;
+
+/// The interface of a sequence that gives O(1) access to its items by index
+pub trait RandomAccessSeq {
+    type Item;
+
+    fn len(&self) -> usize;
+
+    /// Returns the item at `index`, panics if the index is out of bounds
+    fn get(&self, index: usize) -> Self::Item;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn index_iter(self) -> IndexIter<Self>
+    where
+        Self: Sized,
+    {
+        IndexIter::new(self)
+    }
+}
+
+impl<'a, T> RandomAccessSeq for &'a [T] {
+    type Item = &'a T;
+
+    #[inline]
+    fn len(&self) -> usize {
+        <[T]>::len(self)
+    }
+
+    #[inline]
+    fn get(&self, index: usize) -> &'a T {
+        &self[index]
+    }
+}
+
+impl<'a, T, const N: usize> RandomAccessSeq for &'a [T; N] {
+    type Item = &'a T;
+
+    #[inline]
+    fn len(&self) -> usize {
+        N
+    }
+
+    #[inline]
+    fn get(&self, index: usize) -> &'a T {
+        &self[index]
+    }
+}
+
+impl<'a, T> RandomAccessSeq for &'a Vec<T> {
+    type Item = &'a T;
+
+    #[inline]
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+
+    #[inline]
+    fn get(&self, index: usize) -> &'a T {
+        &self[index]
+    }
+}
+
+/// A `SequenceIterator` over a `RandomAccessSeq`, which `nth` and `nth_back`
+/// take O(1) time, so splitting a `SeqIter` over it is O(1) too.
+#[derive(Clone, Debug)]
+pub struct IndexIter<S> {
+    seq: S,
+    front: usize,
+    back: usize,
+}
+
+impl<S: RandomAccessSeq> IndexIter<S> {
+    pub fn new(seq: S) -> Self {
+        let back = seq.len();
+        Self { seq, front: 0, back }
+    }
+}
+
+impl<S: RandomAccessSeq> Iterator for IndexIter<S> {
+    type Item = S::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<S::Item> {
+        if self.front < self.back {
+            self.front += 1;
+            Some(self.seq.get(self.front - 1))
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+
+    #[inline]
+    fn nth(&mut self, n: usize) -> Option<S::Item> {
+        if n < self.back - self.front {
+            self.front += n;
+            self.next()
+        } else {
+            self.front = self.back;
+            None
+        }
+    }
+}
+
+impl<S: RandomAccessSeq> DoubleEndedIterator for IndexIter<S> {
+    #[inline]
+    fn next_back(&mut self) -> Option<S::Item> {
+        if self.front < self.back {
+            self.back -= 1;
+            Some(self.seq.get(self.back))
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    fn nth_back(&mut self, n: usize) -> Option<S::Item> {
+        if n < self.back - self.front {
+            self.back -= n;
+            self.next_back()
+        } else {
+            self.back = self.front;
+            None
+        }
+    }
+}
+
+impl<S: RandomAccessSeq> ExactSizeIterator for IndexIter<S> {}
+
+impl<S: RandomAccessSeq> FusedIterator for IndexIter<S> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SeqIter;
+
+    #[test]
+    fn test_index_iter() {
+        let a = vec![1, 2, 3, 4, 5];
+
+        let mut iter = (&a).index_iter();
+        assert_eq!(5, iter.len());
+        assert_eq!(Some(&1), iter.next());
+        assert_eq!(Some(&5), iter.next_back());
+        assert_eq!(3, iter.len());
+        assert_eq!(Some(&3), iter.nth(1));
+        assert_eq!(Some(&4), iter.nth_back(0));
+        assert_eq!(None, iter.next());
+        assert_eq!(0, iter.len());
+
+        let mut iter = (&a[..]).index_iter();
+        assert_eq!(None, iter.nth(5));
+        assert_eq!(None, iter.next_back());
+
+        let mut iter = SeqIter::new((&a).index_iter(), a.len()).skip(1).take(3).rev();
+        assert_eq!(3, iter.len());
+        assert_eq!(Some(&4), iter.next());
+        assert_eq!(Some(&3), iter.next());
+        assert_eq!(Some(&2), iter.next());
+        assert_eq!(None, iter.next());
+    }
+}
@@ -0,0 +1,414 @@
+//! The global alignment with the affine gap scores, where a gap of `k` items
+//! scores `gap_open + k * gap`, by the Gotoh's recurrence and the Myers–Miller
+//! divide and conquer, in O(mn) time and O(m + n) space.
+//!
+//! Gotoh, O. (1982). "An improved algorithm for matching biological sequences".
+//! Journal of Molecular Biology. 162 (3): 705–708.
+//!
+//! Myers, E. W.; Miller, W. (1988). "Optimal alignments in linear space".
+//! Computer Applications in the Biosciences. 4 (1): 11–17.
+
+use crate::{EditScript, Scoring, SeqIter, SequenceIterator};
+
+/// The scoring that adds the `gap_open` score to every gap of the `scoring`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Affine<S> {
+    pub scoring: S,
+    pub gap_open: i64,
+}
+
+impl<S> Affine<S> {
+    pub const fn new(scoring: S, gap_open: i64) -> Self {
+        Self { scoring, gap_open }
+    }
+}
+
+impl<T, S: Scoring<T>> Scoring<T> for Affine<S> {
+    #[inline]
+    fn pair(&self, a: &T, b: &T) -> i64 {
+        self.scoring.pair(a, b)
+    }
+
+    #[inline]
+    fn gap(&self) -> i64 {
+        self.scoring.gap()
+    }
+
+    #[inline]
+    fn gap_open(&self) -> i64 {
+        self.gap_open
+    }
+}
+
+/// The cost that is never chosen, it is small enough to be summed up without an overflow
+const INFINITY: i64 = i64::MAX / 4;
+
+/// The costs of the alignment, which are the negated scores, so the recurrence
+/// minimizes them as in the Myers–Miller paper
+struct Costs<'a, S: ?Sized> {
+    scoring: &'a S,
+    open: i64,
+    extend: i64,
+}
+
+impl<'a, S: ?Sized> Costs<'a, S> {
+    fn new<T>(scoring: &'a S) -> Self
+    where
+        S: Scoring<T>,
+    {
+        Self {
+            scoring,
+            open: -scoring.gap_open(),
+            extend: -scoring.gap(),
+        }
+    }
+
+    #[inline]
+    fn pair<T>(&self, a: &T, b: &T) -> i64
+    where
+        S: Scoring<T>,
+    {
+        -self.scoring.pair(a, b)
+    }
+
+    #[inline]
+    fn gap(&self, len: usize) -> i64 {
+        if len == 0 {
+            0
+        } else {
+            self.open + self.extend * len as i64
+        }
+    }
+
+    /// Computes the last lines of the cost matrices of aligning `a` against `b`,
+    /// where a deletion gap at the start of `a` is opened with the cost `open`.
+    /// Returns the minimal costs and the minimal costs that end with a deletion.
+    fn last_lines<IterA, IterB, T>(&self, a: IterA, b: IterB, open: i64) -> (Vec<i64>, Vec<i64>)
+    where
+        IterA: Iterator<Item = T>,
+        IterB: ExactSizeIterator<Item = T> + Clone,
+        S: Scoring<T>,
+    {
+        let mut costs: Vec<i64> = (0..=b.len()).map(|j| self.gap(j)).collect();
+        let mut deletions = vec![INFINITY; b.len() + 1];
+
+        for (i, a) in a.enumerate() {
+            let mut diag = costs[0];
+            let mut cost = open + self.extend * (i as i64 + 1);
+            let mut insertion = INFINITY;
+            costs[0] = cost;
+            deletions[0] = cost;
+
+            for (j, b) in b.clone().enumerate() {
+                let idx = j + 1;
+
+                insertion = insertion.min(cost + self.open) + self.extend;
+                let deletion = deletions[idx].min(costs[idx] + self.open) + self.extend;
+                cost = (diag + self.pair(&a, &b)).min(insertion).min(deletion);
+                diag = costs[idx];
+                costs[idx] = cost;
+                deletions[idx] = deletion;
+            }
+        }
+        (costs, deletions)
+    }
+}
+
+/// Returns the score of the optimal global alignment of `a` and `b`
+/// with the affine gap scores
+pub fn gotoh_score<SeqA, SeqB, S>(a: SeqA, b: SeqB, scoring: &S) -> i64
+where
+    SeqA: IntoIterator,
+    SeqB: IntoIterator<Item = SeqA::Item>,
+    SeqA::IntoIter: ExactSizeIterator,
+    SeqB::IntoIter: ExactSizeIterator + Clone,
+    S: Scoring<SeqA::Item> + ?Sized,
+{
+    let costs = Costs::new(scoring);
+    let (last, _) = costs.last_lines(a.into_iter(), b.into_iter(), costs.open);
+    -last[last.len() - 1]
+}
+
+/// Computes the optimal global alignment of `a` and `b` with the affine gap
+/// scores of the `scoring` in O(mn) time and O(m + n) space. The aligned pairs
+/// of equal items are pushed as both, and the rest of them as replaced.
+pub fn gotoh<SeqA, SeqB, SeqC, S>(a: SeqA, b: SeqB, scoring: &S) -> SeqC
+where
+    SeqA: IntoIterator,
+    SeqA::Item: PartialEq,
+    SeqB: IntoIterator<Item = SeqA::Item>,
+    SeqA::IntoIter: SequenceIterator,
+    SeqB::IntoIter: SequenceIterator,
+    SeqC: EditScript<SeqA::Item>,
+    S: Scoring<SeqA::Item> + ?Sized,
+{
+    /// Aligns `a` against `b`, where `top` and `bottom` are the costs of opening
+    /// a deletion gap at the start and at the end of `a`, which are zero if the gap
+    /// continues the deletion of the adjacent sub-problem
+    fn gotoh_inner<IterA, IterB, SeqC, S>(
+        mut a: SeqIter<IterA>, b: SeqIter<IterB>, diff: &mut SeqC, costs: &Costs<'_, S>, top: i64, bottom: i64,
+    ) where
+        IterA: SequenceIterator,
+        IterA::Item: PartialEq,
+        IterB: SequenceIterator + Iterator<Item = IterA::Item>,
+        SeqC: EditScript<IterA::Item>,
+        S: Scoring<IterA::Item> + ?Sized,
+    {
+        let (a_len, b_len) = (a.len(), b.len());
+
+        if b_len == 0 {
+            for a_item in a {
+                diff.push_first(a_item);
+            }
+        } else if a_len == 0 {
+            for b_item in b {
+                diff.push_second(b_item);
+            }
+        } else if a_len == 1 {
+            let a_item = a.next().unwrap();
+
+            let mut min = top.min(bottom) + costs.extend + costs.gap(b_len);
+            let mut k = None;
+            for (j, b_item) in b.clone().enumerate() {
+                let cost = costs.gap(j) + costs.pair(&a_item, &b_item) + costs.gap(b_len - j - 1);
+                if cost < min {
+                    min = cost;
+                    k = Some(j);
+                }
+            }
+
+            match k {
+                Some(k) => {
+                    let mut b = b.enumerate();
+                    for (_, b_item) in b.by_ref().take(k) {
+                        diff.push_second(b_item);
+                    }
+                    let (_, b_item) = b.next().unwrap();
+                    if a_item == b_item {
+                        diff.push_both(b_item);
+                    } else {
+                        diff.push_replace(a_item, b_item);
+                    }
+                    for (_, b_item) in b {
+                        diff.push_second(b_item);
+                    }
+                }
+                // The deletion is joined with the adjacent one if it is cheaper
+                None if bottom < top => {
+                    for b_item in b {
+                        diff.push_second(b_item);
+                    }
+                    diff.push_first(a_item);
+                }
+                None => {
+                    diff.push_first(a_item);
+                    for b_item in b {
+                        diff.push_second(b_item);
+                    }
+                }
+            }
+        } else {
+            let mid = a_len / 2;
+            let (costs_left, deletions_left) = costs.last_lines(a.clone().take(mid), b.clone(), top);
+            let (costs_right, deletions_right) = costs.last_lines(a.clone().skip(mid).rev(), b.clone().rev(), bottom);
+
+            // The split either passes between the items, or through a deletion
+            // gap that covers both middle items of `a` and is opened once
+            let mut k = 0;
+            let mut through_gap = false;
+            let mut min = INFINITY;
+            for j in 0..=b_len {
+                let cost = costs_left[j] + costs_right[b_len - j];
+                if cost < min {
+                    min = cost;
+                    k = j;
+                    through_gap = false;
+                }
+                let cost = deletions_left[j] + deletions_right[b_len - j] - costs.open;
+                if cost < min {
+                    min = cost;
+                    k = j;
+                    through_gap = true;
+                }
+            }
+
+            if through_gap {
+                gotoh_inner(a.clone().take(mid - 1), b.clone().take(k), diff, costs, top, 0);
+                for a_item in a.clone().skip(mid - 1).take(2) {
+                    diff.push_first(a_item);
+                }
+                gotoh_inner(a.skip(mid + 1), b.skip(k), diff, costs, 0, bottom);
+            } else {
+                gotoh_inner(a.clone().take(mid), b.clone().take(k), diff, costs, top, costs.open);
+                gotoh_inner(a.skip(mid), b.skip(k), diff, costs, costs.open, bottom);
+            }
+        }
+    }
+
+    let costs = Costs::new(scoring);
+    let (a, b) = (a.into_iter(), b.into_iter());
+    let (a_len, b_len) = (a.len(), b.len());
+    let mut diff = SeqC::empty();
+    gotoh_inner(
+        SeqIter::new(a, a_len),
+        SeqIter::new(b, b_len),
+        &mut diff,
+        &costs,
+        costs.open,
+        costs.open,
+    );
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{nw_score, AsCharsIter, EditItem, Edits, SimpleScoring};
+
+    #[test]
+    fn test_gotoh_score() {
+        let scoring = Affine::new(SimpleScoring::new(1, -1, -1), 0);
+        for (a, b) in &[("", ""), ("GCATGCU", "GATTACA"), ("AGTACGCA", "TATGC"), ("abc", "")] {
+            assert_eq!(
+                gotoh_score(a.chars_iter(), b.chars_iter(), &scoring),
+                nw_score(a.chars_iter(), b.chars_iter(), &scoring)
+            );
+        }
+
+        let scoring = Affine::new(SimpleScoring::new(1, -1, -1), -3);
+        assert_eq!(gotoh_score("".chars_iter(), "".chars_iter(), &scoring), 0);
+        assert_eq!(gotoh_score("abcd".chars_iter(), "".chars_iter(), &scoring), -7);
+        assert_eq!(gotoh_score("abcd".chars_iter(), "ad".chars_iter(), &scoring), -3);
+        assert_eq!(gotoh_score("abxd".chars_iter(), "abyd".chars_iter(), &scoring), 2);
+
+        let mut random = Random(7);
+        for _ in 0..200 {
+            let (a, b) = (random.string(12), random.string(12));
+            assert_eq!(
+                gotoh_score(a.chars_iter(), b.chars_iter(), &scoring),
+                full_gotoh_score(&a, &b, &scoring)
+            );
+        }
+    }
+
+    #[test]
+    fn test_gotoh() {
+        let scoring = Affine::new(SimpleScoring::new(2, -1, -1), -4);
+
+        let Edits(diff): Edits<Vec<_>> = gotoh(b"", b"", &scoring);
+        assert_eq!(diff, Vec::<EditItem<&u8>>::new());
+
+        let Edits(diff): Edits<Vec<_>> = gotoh("abcd".chars_iter(), "".chars_iter(), &scoring);
+        assert_eq!(diff, "abcd".chars().map(EditItem::First).collect::<Vec<_>>());
+
+        // The linear gaps split the deletion, the affine ones keep it contiguous
+        let (a, b) = ("fn a() {}\nfn b() {}", "fn b() {}");
+        let Edits(diff): Edits<Vec<_>> = gotoh(a.chars_iter(), b.chars_iter(), &scoring);
+        assert_eq!(gap_count(&diff), 1);
+
+        let mut random = Random(11);
+        for _ in 0..200 {
+            let (a, b) = (random.string(16), random.string(16));
+            let Edits(diff): Edits<Vec<_>> = gotoh(a.chars_iter(), b.chars_iter(), &scoring);
+            assert_eq!(diff_score(&diff, &scoring), full_gotoh_score(&a, &b, &scoring));
+            assert_eq!(first_of(&diff), a);
+            assert_eq!(second_of(&diff), b);
+        }
+    }
+
+    struct Random(u64);
+
+    impl Random {
+        fn string(&mut self, max_len: usize) -> String {
+            let len = self.next() as usize % (max_len + 1);
+            (0..len).map(|_| (b'a' + (self.next() % 3) as u8) as char).collect()
+        }
+
+        fn next(&mut self) -> u64 {
+            self.0 = self
+                .0
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            self.0 >> 33
+        }
+    }
+
+    /// The Gotoh's recurrence over the full matrices
+    fn full_gotoh_score(a: &str, b: &str, scoring: &Affine<SimpleScoring>) -> i64 {
+        let (a, b): (Vec<_>, Vec<_>) = (a.chars().collect(), b.chars().collect());
+        let (open, extend) = (scoring.gap_open, Scoring::<char>::gap(scoring));
+        let min = i64::MIN / 4;
+        let mut best = vec![vec![min; b.len() + 1]; a.len() + 1];
+        let mut deletion = best.clone();
+        let mut insertion = best.clone();
+        best[0][0] = 0;
+        for i in 0..=a.len() {
+            for j in 0..=b.len() {
+                if i > 0 {
+                    deletion[i][j] = (deletion[i - 1][j] + extend).max(best[i - 1][j] + open + extend);
+                }
+                if j > 0 {
+                    insertion[i][j] = (insertion[i][j - 1] + extend).max(best[i][j - 1] + open + extend);
+                }
+                if i > 0 || j > 0 {
+                    best[i][j] = deletion[i][j].max(insertion[i][j]);
+                }
+                if i > 0 && j > 0 {
+                    best[i][j] = best[i][j].max(best[i - 1][j - 1] + scoring.pair(&a[i - 1], &b[j - 1]));
+                }
+            }
+        }
+        best[a.len()][b.len()]
+    }
+
+    fn diff_score(diff: &[EditItem<char>], scoring: &Affine<SimpleScoring>) -> i64 {
+        let mut score = 0;
+        let mut prev = None;
+        for item in diff {
+            score += match item {
+                EditItem::Both(x) => scoring.pair(x, x),
+                EditItem::Replace(x, y) => scoring.pair(x, y),
+                EditItem::First(_) if prev == Some(-1) => Scoring::<char>::gap(scoring),
+                EditItem::Second(_) if prev == Some(1) => Scoring::<char>::gap(scoring),
+                EditItem::First(_) | EditItem::Second(_) => scoring.gap_open + Scoring::<char>::gap(scoring),
+            };
+            prev = match item {
+                EditItem::First(_) => Some(-1),
+                EditItem::Second(_) => Some(1),
+                _ => None,
+            };
+        }
+        score
+    }
+
+    fn gap_count(diff: &[EditItem<char>]) -> usize {
+        let mut count = 0;
+        let mut in_gap = false;
+        for item in diff {
+            let is_gap = matches!(item, EditItem::First(_) | EditItem::Second(_));
+            if is_gap && !in_gap {
+                count += 1;
+            }
+            in_gap = is_gap;
+        }
+        count
+    }
+
+    fn first_of(diff: &[EditItem<char>]) -> String {
+        diff.iter()
+            .filter_map(|item| match item {
+                EditItem::First(x) | EditItem::Both(x) | EditItem::Replace(x, _) => Some(*x),
+                EditItem::Second(_) => None,
+            })
+            .collect()
+    }
+
+    fn second_of(diff: &[EditItem<char>]) -> String {
+        diff.iter()
+            .filter_map(|item| match item {
+                EditItem::Second(x) | EditItem::Both(x) | EditItem::Replace(_, x) => Some(*x),
+                EditItem::First(_) => None,
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,350 @@
+use crate::{
+    limited_hirschberg_diff, Bounded, EditScript, Interrupted, Limit, NoLimit, SequenceIterator, SplitScoring,
+    LIMIT_CHECK_CELLS,
+};
+
+/// The interface of a scoring scheme of the global alignment, which maximizes
+/// the total score of the aligned pairs and gaps
+pub trait Scoring<T> {
+    /// The score of aligning `a` against `b`, whether they match or not
+    fn pair(&self, a: &T, b: &T) -> i64;
+
+    /// The score of aligning an item against a gap, usually negative
+    fn gap(&self) -> i64;
+
+    /// The score added once to every gap, which only the affine gap alignment takes into account
+    fn gap_open(&self) -> i64 {
+        0
+    }
+}
+
+/// The scoring with the fixed match and mismatch scores
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SimpleScoring {
+    pub matched: i64,
+    pub mismatched: i64,
+    pub gap: i64,
+}
+
+impl SimpleScoring {
+    /// The scoring of the longest common subsequence, which never aligns mismatched items
+    pub const LCS: Self = Self::new(1, 0, 0);
+
+    /// The scoring of the Levenshtein distance negated
+    pub const LEVENSHTEIN: Self = Self::new(0, -1, -1);
+
+    pub const fn new(matched: i64, mismatched: i64, gap: i64) -> Self {
+        Self {
+            matched,
+            mismatched,
+            gap,
+        }
+    }
+}
+
+impl<T: PartialEq> Scoring<T> for SimpleScoring {
+    #[inline]
+    fn pair(&self, a: &T, b: &T) -> i64 {
+        if a == b {
+            self.matched
+        } else {
+            self.mismatched
+        }
+    }
+
+    #[inline]
+    fn gap(&self) -> i64 {
+        self.gap
+    }
+}
+
+/// This function returns the last line of the Needleman-Wunsch score matrix
+/// computed with the `scoring`
+pub fn nw_score_last_line<SeqA, SeqB, S>(a: SeqA, b: SeqB, scoring: &S) -> Vec<i64>
+where
+    SeqA: IntoIterator,
+    SeqB: IntoIterator<Item = SeqA::Item>,
+    SeqA::IntoIter: ExactSizeIterator,
+    SeqB::IntoIter: ExactSizeIterator + Clone,
+    S: Scoring<SeqA::Item> + ?Sized,
+{
+    match score_rows(a.into_iter(), b.into_iter(), scoring, false, &NoLimit, |_, _, _| ()) {
+        Ok(line) => line,
+        Err(Interrupted) => unreachable!("NoLimit is never exceeded"),
+    }
+}
+
+/// Computes the score matrix row by row in linear space and returns its last
+/// line, or stops with an error once the `limit` is exceeded. The `visit` is
+/// called with the indices and the score of every cell of an item of `a`
+/// against an item of `b`. The local scores never fall below zero, as in
+/// the Smith-Waterman algorithm.
+pub(crate) fn score_rows<IterA, IterB, S, L>(
+    a: IterA, b: IterB, scoring: &S, local: bool, limit: &L, mut visit: impl FnMut(usize, usize, i64),
+) -> Result<Vec<i64>, Interrupted>
+where
+    IterA: Iterator,
+    IterB: ExactSizeIterator<Item = IterA::Item> + Clone,
+    S: Scoring<IterA::Item> + ?Sized,
+    L: Limit + ?Sized,
+{
+    let gap = scoring.gap();
+    let floor = if local { 0 } else { i64::MIN };
+    let mut line: Vec<i64> = (0..=b.len() as i64).map(|j| (j * gap).max(floor)).collect();
+    let mut unchecked_cells = 0;
+
+    for (i, a) in a.enumerate() {
+        unchecked_cells += line.len();
+        if unchecked_cells >= LIMIT_CHECK_CELLS {
+            if limit.is_exceeded() {
+                return Err(Interrupted);
+            }
+            unchecked_cells = 0;
+        }
+
+        let mut diag = line[0];
+        line[0] = (line[0] + gap).max(floor);
+        for (j, b) in b.clone().enumerate() {
+            let idx = j + 1;
+
+            let up = line[idx];
+            let last = (diag + scoring.pair(&a, &b))
+                .max(up + gap)
+                .max(line[j] + gap)
+                .max(floor);
+            diag = up;
+            line[idx] = last;
+            visit(i + 1, idx, last);
+        }
+    }
+    Ok(line)
+}
+
+/// Returns the score of the optimal global alignment of `a` and `b`
+pub fn nw_score<SeqA, SeqB, S>(a: SeqA, b: SeqB, scoring: &S) -> i64
+where
+    SeqA: IntoIterator,
+    SeqB: IntoIterator<Item = SeqA::Item>,
+    SeqA::IntoIter: ExactSizeIterator,
+    SeqB::IntoIter: ExactSizeIterator + Clone,
+    S: Scoring<SeqA::Item> + ?Sized,
+{
+    let line = nw_score_last_line(a, b, scoring);
+    line[line.len() - 1]
+}
+
+/// Computes the optimal global alignment of `a` and `b` with the `scoring` by
+/// the Hirschberg's technique in O(mn) time and O(m + n) space. The aligned
+/// pairs of equal items are pushed as both, and the rest of them as replaced.
+pub fn needleman_wunsch<SeqA, SeqB, SeqC, S>(a: SeqA, b: SeqB, scoring: &S) -> SeqC
+where
+    SeqA: IntoIterator,
+    SeqA::Item: PartialEq,
+    SeqB: IntoIterator<Item = SeqA::Item>,
+    SeqA::IntoIter: SequenceIterator,
+    SeqB::IntoIter: SequenceIterator,
+    SeqC: EditScript<SeqA::Item>,
+    S: Scoring<SeqA::Item> + ?Sized,
+{
+    match limited_hirschberg_diff(a, b, &Weighted(scoring), 0, &NoLimit, false) {
+        Ok(Bounded { value, .. }) => value,
+        Err(Interrupted) => unreachable!("NoLimit is never exceeded"),
+    }
+}
+
+/// The scoring that splits the Hirschberg's recursion by the signed score lines
+pub(crate) struct Weighted<'a, S: ?Sized>(pub(crate) &'a S);
+
+impl<T, S: Scoring<T> + ?Sized> Scoring<T> for Weighted<'_, S> {
+    #[inline]
+    fn pair(&self, a: &T, b: &T) -> i64 {
+        self.0.pair(a, b)
+    }
+
+    #[inline]
+    fn gap(&self) -> i64 {
+        self.0.gap()
+    }
+
+    #[inline]
+    fn gap_open(&self) -> i64 {
+        self.0.gap_open()
+    }
+}
+
+impl<T, S: Scoring<T> + ?Sized> SplitScoring<T> for Weighted<'_, S> {
+    type Line = Vec<i64>;
+
+    fn last_line<IterA, IterB, L>(&self, a: IterA, b: IterB, limit: &L) -> Result<Vec<i64>, Interrupted>
+    where
+        IterA: ExactSizeIterator<Item = T>,
+        IterB: ExactSizeIterator<Item = T> + Clone,
+        L: Limit + ?Sized,
+    {
+        score_rows(a, b, self.0, false, limit, |_, _, _| ())
+    }
+
+    #[inline]
+    fn score(line: &Vec<i64>, j: usize) -> i64 {
+        line[j]
+    }
+}
+
+/// Computes the edit script of the minimal Levenshtein distance between `a`
+/// and `b`, where a substitution is a single edit, by the Hirschberg's
+/// technique in O(mn) time and O(m + n) space.
+pub fn levenshtein_diff<SeqA, SeqB, SeqC>(a: SeqA, b: SeqB) -> SeqC
+where
+    SeqA: IntoIterator,
+    SeqA::Item: PartialEq,
+    SeqB: IntoIterator<Item = SeqA::Item>,
+    SeqA::IntoIter: SequenceIterator,
+    SeqB::IntoIter: SequenceIterator,
+    SeqC: EditScript<SeqA::Item>,
+{
+    needleman_wunsch(a, b, &SimpleScoring::LEVENSHTEIN)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Display;
+
+    use super::*;
+    use crate::{levenshtein, AsCharsIter, Diff, DiffItem, EditItem, Edits};
+
+    #[test]
+    fn test_nw_score_last_line() {
+        let last = nw_score_last_line(b"", b"", &SimpleScoring::new(1, -1, -1));
+        assert_eq!(last, vec![0]);
+
+        let last = nw_score_last_line(b"", b"TATGC", &SimpleScoring::new(1, -1, -2));
+        assert_eq!(last, vec![0, -2, -4, -6, -8, -10]);
+
+        let last = nw_score_last_line(b"GCATGCU", b"GATTACA", &SimpleScoring::new(1, -1, -1));
+        assert_eq!(last, vec![-7, -5, -3, -1, -1, -1, 0, 0]);
+
+        let last = nw_score_last_line(b"AGTACGCA", b"TATGC", &SimpleScoring::LCS);
+        assert_eq!(last, vec![0, 1, 2, 2, 3, 4]);
+
+        let (a, b) = ("abcdfghjqvz", "abcdefgijkrxyz");
+        let score = nw_score(a.chars_iter(), b.chars_iter(), &SimpleScoring::LEVENSHTEIN);
+        assert_eq!(-score as usize, levenshtein(a.chars_iter(), b.chars_iter()));
+    }
+
+    #[test]
+    fn test_needleman_wunsch() {
+        let scoring = SimpleScoring::new(1, -1, -1);
+
+        let Edits(diff): Edits<Vec<_>> = needleman_wunsch(b"", b"", &scoring);
+        assert_eq!(diff, Vec::<EditItem<&u8>>::new());
+
+        let Edits(diff): Edits<Vec<_>> = needleman_wunsch("GCATGCU".chars_iter(), "GATTACA".chars_iter(), &scoring);
+        assert_eq!(format_edits(diff), "G-CAT(G|T)+AC(U|A)".to_string());
+
+        let Edits(diff): Edits<Vec<_>> = needleman_wunsch(
+            "AGTACGCA".chars_iter(),
+            "TATGC".chars_iter(),
+            &SimpleScoring::new(2, -1, -2),
+        );
+        assert_eq!(format_edits(diff), "-A-GTA(C|T)GC-A".to_string());
+
+        let Diff(diff): Diff<Vec<_>> =
+            needleman_wunsch("ABCBDAB".chars_iter(), "BDCABA".chars_iter(), &SimpleScoring::LCS);
+        let both = diff.iter().filter(|item| matches!(item, DiffItem::Both(_))).count();
+        assert_eq!(both, 4);
+
+        let (a, b) = ("abcdfghjqvz", "abcdefgijkrxyz");
+        let scoring = SimpleScoring::new(3, -2, -1);
+        let Edits(diff): Edits<Vec<_>> = needleman_wunsch(a.chars_iter(), b.chars_iter(), &scoring);
+        assert_eq!(
+            edits_score(&diff, &scoring),
+            nw_score(a.chars_iter(), b.chars_iter(), &scoring)
+        );
+    }
+
+    #[test]
+    fn test_full_matrix_alignment() {
+        let pairs = [
+            ("GCATGCU", "GATTACA"),
+            ("abcdfghjqvz", "abcdefgijkrxyz"),
+            ("kitten", "sitting"),
+        ];
+        for scoring in &[
+            SimpleScoring::new(1, -1, -1),
+            SimpleScoring::new(2, -1, -2),
+            SimpleScoring::LEVENSHTEIN,
+        ] {
+            for (a, b) in &pairs {
+                for cells in &[4, 16, 1024] {
+                    let diff: Bounded<Edits<Vec<_>>> = limited_hirschberg_diff(
+                        a.chars_iter(),
+                        b.chars_iter(),
+                        &Weighted(scoring),
+                        *cells,
+                        &NoLimit,
+                        false,
+                    )
+                    .unwrap();
+                    assert_eq!(
+                        edits_score(&diff.value.0, scoring),
+                        nw_score(a.chars_iter(), b.chars_iter(), scoring)
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_levenshtein_diff() {
+        let Edits(diff): Edits<Vec<_>> = levenshtein_diff(b"", b"");
+        assert_eq!(diff, Vec::<EditItem<&u8>>::new());
+
+        let Edits(diff): Edits<Vec<_>> = levenshtein_diff("abc".chars_iter(), "".chars_iter());
+        assert_eq!(format_edits(diff), "-a-b-c".to_string());
+
+        let Edits(diff): Edits<Vec<_>> = levenshtein_diff("".chars_iter(), "abc".chars_iter());
+        assert_eq!(format_edits(diff), "+a+b+c".to_string());
+
+        let Edits(diff): Edits<Vec<_>> = levenshtein_diff("kitten".chars_iter(), "sitting".chars_iter());
+        assert_eq!(format_edits(diff), "(k|s)itt(e|i)n+g".to_string());
+
+        let Edits(diff): Edits<Vec<_>> =
+            levenshtein_diff(vec!["a = 1", "b = 2", "c = 3"], vec!["a = 1", "b = 5", "c = 3"]);
+        assert_eq!(
+            diff,
+            vec![
+                EditItem::Both("a = 1"),
+                EditItem::Replace("b = 2", "b = 5"),
+                EditItem::Both("c = 3")
+            ]
+        );
+
+        let (a, b) = ("abcdfghjqvz", "abcdefgijkrxyz");
+        let Edits(diff): Edits<Vec<_>> = levenshtein_diff(a.chars_iter(), b.chars_iter());
+        let edits = diff.iter().filter(|item| !matches!(item, EditItem::Both(_))).count();
+        assert_eq!(edits, levenshtein(a.chars_iter(), b.chars_iter()));
+    }
+
+    fn edits_score(diff: &[EditItem<char>], scoring: &SimpleScoring) -> i64 {
+        diff.iter()
+            .map(|item| match item {
+                EditItem::Both(x) => scoring.pair(x, x),
+                EditItem::Replace(x, y) => scoring.pair(x, y),
+                EditItem::First(_) | EditItem::Second(_) => Scoring::<char>::gap(scoring),
+            })
+            .sum()
+    }
+
+    fn format_edits(diff: Vec<EditItem<impl Display>>) -> String {
+        let mut line = String::new();
+        for item in diff {
+            match item {
+                EditItem::First(x) => line.push_str(&format!("-{}", x)),
+                EditItem::Both(x) => line.push_str(&x.to_string()),
+                EditItem::Second(x) => line.push_str(&format!("+{}", x)),
+                EditItem::Replace(x, y) => line.push_str(&format!("({}|{})", x, y)),
+            }
+        }
+        line
+    }
+}
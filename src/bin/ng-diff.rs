@@ -0,0 +1,694 @@
+//! The command line tool that compares two files or directories like GNU diff.
+//! The exit status is 0 if the files are the same, 1 if they differ
+//! and 2 if there was trouble.
+
+use std::{
+    env, fs,
+    io::{self, Read, Write},
+    path::Path,
+    process,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use ng_diff::{
+    color::{self, ColorMode, ColorOptions, Emphasis},
+    diff_contents,
+    dir::{compare_dirs, DirEntry, DirOptions},
+    format::{self, SideBySideOptions},
+    html::{self, HtmlLayout, HtmlOptions},
+    Hirschberg, HirschbergAlg, HybridHirschberg, LineDiff,
+};
+
+const USAGE: &str = "Usage: ng-diff [OPTION]... FILES
+Compare FILES line by line, '-' stands for the standard input.
+FILES are 'FILE1 FILE2', 'DIR1 DIR2', 'DIR FILE' or 'FILE DIR'.
+
+  -u, -U NUM, --unified[=NUM]  output NUM (default 3) lines of unified context
+  -c, -C NUM, --context[=NUM]  output NUM (default 3) lines of copied context
+  -y, --side-by-side           output in two columns
+  -W, --width=NUM              output at most NUM (default 130) print columns
+      --suppress-common-lines  do not output common lines in two columns
+      --wrap                   wrap long lines in two columns instead of truncating
+      --color[=WHEN]           color the unified and side-by-side output; WHEN is
+                                 'auto' (the default), 'always' or 'never'
+      --palette=SPEC           the colors, e.g. 'del=31:ins=32:del-em=1;7;31:
+                                 ins-em=1;7;32:header=1:hunk=36'
+      --emphasis=UNIT          emphasize the changed 'word's (the default),
+                                 'char's or 'none' within the lines
+      --normal                 output a normal diff (the default)
+      --json                   output the diff as JSON
+      --html[=LAYOUT]          output an HTML page; LAYOUT is 'side-by-side'
+                                 (the default) or 'inline'
+      --granularity=UNIT       compare by 'line' (the default), 'word' or 'char'
+      --algorithm=NAME         use 'hirschberg' (the default) or 'hybrid'
+  -r, --recursive              recursively compare any subdirectories found
+  -N, --new-file               treat absent files as empty
+  -x, --exclude=PAT            skip files and directories that match PAT
+  -h, --help                   display this help and exit
+  -V, --version                output version information and exit
+
+The word and char granularities are supported by the normal format,
+which marks the changes inline, and by the JSON format. The HTML page
+shows the diff of two files, so it does not compare directories.
+
+Exit status is 0 if inputs are the same, 1 if different, 2 if trouble.
+";
+
+const DEFAULT_CONTEXT: usize = 3;
+const DEFAULT_WIDTH: usize = 130;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Format {
+    Normal,
+    Unified(usize),
+    Context(usize),
+    SideBySide,
+    Json,
+    Html(HtmlLayout),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Granularity {
+    Line,
+    Word,
+    Char,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Algorithm {
+    Hirschberg,
+    Hybrid,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Options {
+    format: Format,
+    granularity: Granularity,
+    algorithm: Algorithm,
+    width: usize,
+    wrap: bool,
+    suppress_common: bool,
+    color: ColorMode,
+    colors: ColorOptions,
+    recursive: bool,
+    new_file: bool,
+    exclude: Vec<String>,
+    paths: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Command {
+    Diff(Box<Options>),
+    Help,
+    Version,
+}
+
+fn main() {
+    let code = match parse_args(env::args().skip(1)) {
+        Ok(Command::Help) => {
+            print!("{}", USAGE);
+            0
+        }
+        Ok(Command::Version) => {
+            println!("ng-diff {}", env!("CARGO_PKG_VERSION"));
+            0
+        }
+        Ok(Command::Diff(options)) => run(&options),
+        Err(error) => {
+            eprintln!("ng-diff: {}", error);
+            eprintln!("ng-diff: Try 'ng-diff --help' for more information.");
+            2
+        }
+    };
+    process::exit(code);
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Command, String> {
+    let mut options = Options {
+        format: Format::Normal,
+        granularity: Granularity::Line,
+        algorithm: Algorithm::Hirschberg,
+        width: DEFAULT_WIDTH,
+        wrap: false,
+        suppress_common: false,
+        color: ColorMode::Auto,
+        colors: ColorOptions::default(),
+        recursive: false,
+        new_file: false,
+        exclude: Vec::new(),
+        paths: Vec::new(),
+    };
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            options.paths.extend(args.by_ref());
+            break;
+        }
+        if arg == "-" || !arg.starts_with('-') {
+            options.paths.push(arg);
+            continue;
+        }
+
+        let (name, value) = match arg.split_once('=') {
+            Some((name, value)) if arg.starts_with("--") => (name.to_string(), Some(value.to_string())),
+            _ if !arg.starts_with("--") => {
+                let split = arg.char_indices().nth(2).map_or(arg.len(), |(index, _)| index);
+                let value = Some(arg[split..].to_string()).filter(|value| !value.is_empty());
+                (arg[..split].to_string(), value)
+            }
+            _ => (arg.clone(), None),
+        };
+        let takes_value = matches!(
+            name.as_str(),
+            "-U" | "-C"
+                | "-W"
+                | "-x"
+                | "--unified"
+                | "--context"
+                | "--width"
+                | "--exclude"
+                | "--granularity"
+                | "--algorithm"
+                | "--color"
+                | "--palette"
+                | "--emphasis"
+                | "--html"
+        );
+        if value.is_some() && !takes_value {
+            return Err(format!("unrecognized option '{}'", arg));
+        }
+        let mut required_value = |value: Option<String>| {
+            value
+                .or_else(|| args.next())
+                .ok_or_else(|| format!("option '{}' requires an argument", name))
+        };
+
+        match name.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-V" | "--version" => return Ok(Command::Version),
+            "--normal" => options.format = Format::Normal,
+            "--json" => options.format = Format::Json,
+            "--html" => {
+                options.format = match value.as_deref().unwrap_or("side-by-side") {
+                    "side-by-side" => Format::Html(HtmlLayout::SideBySide),
+                    "inline" => Format::Html(HtmlLayout::Inline),
+                    layout => return Err(format!("invalid HTML layout '{}'", layout)),
+                }
+            }
+            "-y" | "--side-by-side" => options.format = Format::SideBySide,
+            "-u" => options.format = Format::Unified(DEFAULT_CONTEXT),
+            "-c" => options.format = Format::Context(DEFAULT_CONTEXT),
+            "--unified" => options.format = Format::Unified(parse_number(&name, value, DEFAULT_CONTEXT)?),
+            "--context" => options.format = Format::Context(parse_number(&name, value, DEFAULT_CONTEXT)?),
+            "-U" => options.format = Format::Unified(parse_number(&name, Some(required_value(value)?), 0)?),
+            "-C" => options.format = Format::Context(parse_number(&name, Some(required_value(value)?), 0)?),
+            "-W" | "--width" => options.width = parse_number(&name, Some(required_value(value)?), 0)?,
+            "--wrap" => options.wrap = true,
+            "--suppress-common-lines" => options.suppress_common = true,
+            "--color" => {
+                options.color = match value.as_deref().unwrap_or("auto") {
+                    "auto" => ColorMode::Auto,
+                    "always" => ColorMode::Always,
+                    "never" => ColorMode::Never,
+                    when => return Err(format!("invalid color mode '{}'", when)),
+                }
+            }
+            "--palette" => {
+                options.colors.palette = required_value(value)?.parse().map_err(|error| format!("{}", error))?
+            }
+            "--emphasis" => {
+                options.colors.emphasis = match required_value(value)?.as_str() {
+                    "word" => Emphasis::Words,
+                    "char" => Emphasis::Chars,
+                    "none" => Emphasis::None,
+                    unit => return Err(format!("invalid emphasis '{}'", unit)),
+                }
+            }
+            "-r" | "--recursive" => options.recursive = true,
+            "-N" | "--new-file" => options.new_file = true,
+            "-x" | "--exclude" => options.exclude.push(required_value(value)?),
+            "--granularity" => {
+                options.granularity = match required_value(value)?.as_str() {
+                    "line" => Granularity::Line,
+                    "word" => Granularity::Word,
+                    "char" => Granularity::Char,
+                    unit => return Err(format!("invalid granularity '{}'", unit)),
+                }
+            }
+            "--algorithm" => {
+                options.algorithm = match required_value(value)?.as_str() {
+                    "hirschberg" => Algorithm::Hirschberg,
+                    "hybrid" => Algorithm::Hybrid,
+                    algorithm => return Err(format!("invalid algorithm '{}'", algorithm)),
+                }
+            }
+            _ => return Err(format!("unrecognized option '{}'", arg)),
+        }
+    }
+
+    match options.paths.len() {
+        0 => return Err("missing operand".to_string()),
+        1 => return Err(format!("missing operand after '{}'", options.paths[0])),
+        2 => (),
+        _ => return Err(format!("extra operand '{}'", options.paths[2])),
+    }
+    if options.granularity != Granularity::Line && !matches!(options.format, Format::Normal | Format::Json) {
+        return Err("the word and char granularities support only the normal and JSON formats".to_string());
+    }
+    Ok(Command::Diff(Box::new(options)))
+}
+
+fn parse_number(name: &str, value: Option<String>, default: usize) -> Result<usize, String> {
+    match value {
+        Some(value) => value
+            .parse()
+            .map_err(|_| format!("invalid argument '{}' of option '{}'", value, name)),
+        None => Ok(default),
+    }
+}
+
+/// Writes the diff of the files or directories and returns the exit status
+fn run(options: &Options) -> i32 {
+    let (first_path, second_path) = (options.paths[0].as_str(), options.paths[1].as_str());
+    let is_dir = |path: &str| path != "-" && Path::new(path).is_dir();
+
+    let result = match (is_dir(first_path), is_dir(second_path)) {
+        (true, true) if matches!(options.format, Format::Html(_)) => {
+            Err("--html cannot compare directories".to_string())
+        }
+        (true, true) => return run_dirs(options, first_path, second_path),
+        (true, false) => in_dir(first_path, second_path).and_then(|first_path| {
+            let first = read(&first_path)?;
+            diff_files(
+                options,
+                (&first_path, &first),
+                (second_path, &read(second_path)?),
+                false,
+            )
+        }),
+        (false, true) => in_dir(second_path, first_path).and_then(|second_path| {
+            let first = read(first_path)?;
+            diff_files(
+                options,
+                (first_path, &first),
+                (&second_path, &read(&second_path)?),
+                false,
+            )
+        }),
+        (false, false) => read(first_path)
+            .and_then(|first| diff_files(options, (first_path, &first), (second_path, &read(second_path)?), false)),
+    };
+    match result {
+        Ok(is_same) => !is_same as i32,
+        Err(error) => {
+            eprintln!("ng-diff: {}", error);
+            2
+        }
+    }
+}
+
+/// Returns the path of the file with the same name as the `file` in the `dir`
+fn in_dir(dir: &str, file: &str) -> Result<String, String> {
+    match Path::new(file).file_name() {
+        Some(name) if file != "-" => Ok(Path::new(dir).join(name).display().to_string()),
+        _ => Err("cannot compare '-' to a directory".to_string()),
+    }
+}
+
+/// Writes the diffs of the paired files of the directories like `diff -r`
+/// and the entries that exist only in one of them
+fn run_dirs(options: &Options, first_root: &str, second_root: &str) -> i32 {
+    let dir_options = DirOptions {
+        recursive: options.recursive,
+        new_file: options.new_file,
+        exclude: options.exclude.clone(),
+    };
+    let entries = match compare_dirs(Path::new(first_root), Path::new(second_root), &dir_options) {
+        Ok(entries) => entries,
+        Err(error) => {
+            eprintln!("ng-diff: {}", error);
+            return 2;
+        }
+    };
+
+    let (mut is_same, mut is_trouble) = (true, false);
+    for entry in entries {
+        let path = entry.path();
+        let first_path = Path::new(first_root).join(path).display().to_string();
+        let second_path = Path::new(second_root).join(path).display().to_string();
+        let only_in = |root: &str| {
+            let dir = match path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+                Some(parent) => Path::new(root).join(parent).display().to_string(),
+                None => root.to_string(),
+            };
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            format!("Only in {}: {}\n", dir, name)
+        };
+
+        let result = match &entry {
+            DirEntry::Files(_) | DirEntry::DeletedFile(_) | DirEntry::AddedFile(_) => {
+                match entry.read(Path::new(first_root), Path::new(second_root)) {
+                    Ok(Some((first, second))) => {
+                        diff_files(options, (&first_path, &first), (&second_path, &second), true)
+                    }
+                    Ok(None) => Ok(true),
+                    Err(error) => Err(error.to_string()),
+                }
+            }
+            DirEntry::OnlyInFirst(_) => write_output(&only_in(first_root)).map(|_| false),
+            DirEntry::OnlyInSecond(_) => write_output(&only_in(second_root)).map(|_| false),
+            DirEntry::CommonDirs(_) => {
+                write_output(&format!("Common subdirectories: {} and {}\n", first_path, second_path)).map(|_| true)
+            }
+            DirEntry::Mismatch(_) => {
+                let message = if Path::new(&first_path).is_dir() {
+                    format!(
+                        "File {} is a directory while file {} is a regular file\n",
+                        first_path, second_path
+                    )
+                } else {
+                    format!(
+                        "File {} is a regular file while file {} is a directory\n",
+                        first_path, second_path
+                    )
+                };
+                write_output(&message).map(|_| false)
+            }
+            DirEntry::DanglingInFirst(_) => Err(format!("{}: No such file or directory", first_path)),
+            DirEntry::DanglingInSecond(_) => Err(format!("{}: No such file or directory", second_path)),
+            DirEntry::LoopInFirst(_) => Err(format!("{}: recursive directory loop", first_path)),
+            DirEntry::LoopInSecond(_) => Err(format!("{}: recursive directory loop", second_path)),
+        };
+        match result {
+            Ok(is_entry_same) => is_same &= is_entry_same,
+            Err(error) => {
+                eprintln!("ng-diff: {}", error);
+                is_trouble = true;
+            }
+        }
+    }
+
+    if is_trouble {
+        2
+    } else {
+        !is_same as i32
+    }
+}
+
+/// Writes the diff of the files and returns whether they are the same.
+/// The diff of the files in directories is preceded by the `diff` line with the options.
+fn diff_files(options: &Options, first: (&str, &[u8]), second: (&str, &[u8]), header: bool) -> Result<bool, String> {
+    let (first_path, second_path) = (first.0, second.0);
+    let split = match options.granularity {
+        Granularity::Line => format::lines,
+        Granularity::Word => format::words,
+        Granularity::Char => format::chars,
+    };
+    let full_matrix_cells = match options.algorithm {
+        Algorithm::Hirschberg => Hirschberg::FULL_MATRIX_CELLS,
+        Algorithm::Hybrid => HybridHirschberg::FULL_MATRIX_CELLS,
+    };
+    let diff = diff_contents(first.1, second.1, split, full_matrix_cells);
+    let is_same = diff.is_same();
+    let (first_items, second_items, ops) = match diff {
+        LineDiff::Lines { first, second, ops } => (first, second, ops),
+        LineDiff::Binary { .. } if is_same => return Ok(true),
+        LineDiff::Binary { .. } => {
+            write_output(&format::binary((first_path, second_path)))?;
+            return Ok(false);
+        }
+    };
+
+    let labels = (label(first_path), label(second_path));
+    let labels = (labels.0.as_str(), labels.1.as_str());
+    let is_colored = options.color.is_enabled(&io::stdout());
+    let mut output = match options.format {
+        Format::Normal if is_same => String::new(),
+        Format::Normal if options.granularity == Granularity::Line => format::normal(&first_items, &second_items, &ops),
+        Format::Normal => {
+            let mut output = format::inline(&first_items, &second_items, &ops);
+            if !output.ends_with('\n') {
+                output.push('\n');
+            }
+            output
+        }
+        Format::Unified(context_len) if is_colored => {
+            color::unified(&first_items, &second_items, &ops, labels, context_len, &options.colors)
+        }
+        Format::Unified(context_len) => format::unified(&first_items, &second_items, &ops, labels, context_len),
+        Format::Context(context_len) => format::context(&first_items, &second_items, &ops, labels, context_len),
+        Format::SideBySide => {
+            let side_by_side_options = SideBySideOptions {
+                width: options.width,
+                wrap: options.wrap,
+                suppress_common: options.suppress_common,
+            };
+            if is_colored {
+                color::side_by_side(
+                    &first_items,
+                    &second_items,
+                    &ops,
+                    &side_by_side_options,
+                    &options.colors,
+                )
+            } else {
+                format::side_by_side(&first_items, &second_items, &ops, &side_by_side_options)
+            }
+        }
+        Format::Json => format::json(&first_items, &second_items, &ops, (first_path, second_path)),
+        Format::Html(layout) => {
+            let html_options = HtmlOptions {
+                layout,
+                context_len: Some(DEFAULT_CONTEXT),
+                emphasis: options.colors.emphasis,
+            };
+            html::html(
+                &first_items,
+                &second_items,
+                &ops,
+                (first_path, second_path),
+                &html_options,
+            )
+        }
+    };
+    if header && !is_same {
+        output.insert_str(
+            0,
+            &format!("diff{} {} {}\n", switches(options), first_path, second_path),
+        );
+    }
+
+    write_output(&output)?;
+    Ok(is_same)
+}
+
+/// Returns the options that affect the output, as the header of the diffs
+/// of the files in directories shows them
+fn switches(options: &Options) -> String {
+    let mut switches = String::new();
+    if options.recursive {
+        switches.push_str(" -r");
+    }
+    if options.new_file {
+        switches.push_str(" -N");
+    }
+    match options.format {
+        Format::Normal => (),
+        Format::Unified(DEFAULT_CONTEXT) => switches.push_str(" -u"),
+        Format::Unified(context_len) => switches.push_str(&format!(" -U {}", context_len)),
+        Format::Context(DEFAULT_CONTEXT) => switches.push_str(" -c"),
+        Format::Context(context_len) => switches.push_str(&format!(" -C {}", context_len)),
+        Format::SideBySide => {
+            switches.push_str(" -y");
+            if options.suppress_common {
+                switches.push_str(" --suppress-common-lines");
+            }
+        }
+        Format::Json => switches.push_str(" --json"),
+        Format::Html(_) => unreachable!("--html does not compare directories"),
+    }
+    switches
+}
+
+fn write_output(output: &str) -> Result<(), String> {
+    io::stdout()
+        .lock()
+        .write_all(output.as_bytes())
+        .map_err(|error| error.to_string())
+}
+
+fn read(path: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    let result = if path == "-" {
+        io::stdin().lock().read_to_end(&mut bytes).map(|_| ())
+    } else {
+        fs::read(path).map(|content| bytes = content)
+    };
+    result.map_err(|error| format!("{}: {}", path, error))?;
+    Ok(bytes)
+}
+
+/// Returns the path with the modification time of the file, with the current
+/// time for the standard input or with the epoch for an absent file
+fn label(path: &str) -> String {
+    let time = if path == "-" {
+        SystemTime::now()
+    } else {
+        fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .unwrap_or(UNIX_EPOCH)
+    };
+    format!("{}\t{}", path, timestamp(time))
+}
+
+/// Formats the `time` in UTC like GNU diff, e.g. `2024-05-17 09:30:00.000000000 +0000`
+fn timestamp(time: SystemTime) -> String {
+    let duration = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let (secs, nanos) = (duration.as_secs(), duration.subsec_nanos());
+    let (days, secs) = ((secs / 86400) as i64, secs % 86400);
+
+    // The civil date from the days since the epoch by Howard Hinnant's algorithm
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}.{:09} +0000",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60,
+        nanos
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Command, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let options = |format, granularity, width| {
+            Ok(Command::Diff(Box::new(Options {
+                format,
+                granularity,
+                algorithm: Algorithm::Hirschberg,
+                width,
+                wrap: false,
+                suppress_common: false,
+                color: ColorMode::Auto,
+                colors: ColorOptions::default(),
+                recursive: false,
+                new_file: false,
+                exclude: Vec::new(),
+                paths: vec!["a".to_string(), "b".to_string()],
+            })))
+        };
+
+        assert_eq!(parse(&["a", "b"]), options(Format::Normal, Granularity::Line, 130));
+        assert_eq!(
+            parse(&["-u", "a", "b"]),
+            options(Format::Unified(3), Granularity::Line, 130)
+        );
+        assert_eq!(
+            parse(&["-U5", "a", "b"]),
+            options(Format::Unified(5), Granularity::Line, 130)
+        );
+        assert_eq!(
+            parse(&["a", "-U", "0", "b"]),
+            options(Format::Unified(0), Granularity::Line, 130)
+        );
+        assert_eq!(
+            parse(&["--context=1", "a", "b"]),
+            options(Format::Context(1), Granularity::Line, 130)
+        );
+        assert_eq!(
+            parse(&["-y", "-W", "80", "a", "b"]),
+            options(Format::SideBySide, Granularity::Line, 80)
+        );
+        assert_eq!(
+            parse(&["--json", "--granularity", "word", "a", "b"]),
+            options(Format::Json, Granularity::Word, 130)
+        );
+        assert_eq!(
+            parse(&["--html=inline", "a", "b"]),
+            options(Format::Html(HtmlLayout::Inline), Granularity::Line, 130)
+        );
+        assert_eq!(parse(&["--help", "a"]), Ok(Command::Help));
+
+        match parse(&["-r", "-N", "-x", "*.o", "--exclude=target", "-u", "a", "b"]) {
+            Ok(Command::Diff(options)) => {
+                assert!(options.recursive && options.new_file);
+                assert_eq!(options.exclude, vec!["*.o", "target"]);
+                assert_eq!(switches(&options), " -r -N -u");
+            }
+            command => panic!("unexpected {:?}", command),
+        }
+
+        assert!(parse(&["a"]).is_err());
+        assert!(parse(&["a", "b", "c"]).is_err());
+        assert!(parse(&["-z", "a", "b"]).is_err());
+        assert!(parse(&["a", "b", "-x"]).is_err());
+        assert!(parse(&["-u5", "a", "b"]).is_err());
+        assert!(parse(&["-ÿ", "a", "b"]).is_err());
+        assert!(parse(&["--html=table", "a", "b"]).is_err());
+        assert!(parse(&["-U", "x", "a", "b"]).is_err());
+        assert!(parse(&["-u", "--granularity=char", "a", "b"]).is_err());
+        assert!(parse(&["--algorithm=myers", "a", "b"]).is_err());
+        assert!(parse(&["--color=sometimes", "a", "b"]).is_err());
+        assert!(parse(&["--palette=del=red", "a", "b"]).is_err());
+
+        match parse(&["--color", "--palette", "del=91", "--emphasis=char", "a", "b"]) {
+            Ok(Command::Diff(options)) => {
+                assert_eq!(options.color, ColorMode::Auto);
+                assert_eq!(options.colors.palette.deleted, "91");
+                assert_eq!(options.colors.emphasis, Emphasis::Chars);
+            }
+            command => panic!("unexpected {:?}", command),
+        }
+        match parse(&["--color=never", "a", "b"]) {
+            Ok(Command::Diff(options)) => assert_eq!(options.color, ColorMode::Never),
+            command => panic!("unexpected {:?}", command),
+        }
+    }
+
+    #[test]
+    fn test_timestamp() {
+        assert_eq!(timestamp(UNIX_EPOCH), "1970-01-01 00:00:00.000000000 +0000");
+        assert_eq!(
+            timestamp(UNIX_EPOCH + Duration::new(1_709_210_096, 5)),
+            "2024-02-29 12:34:56.000000005 +0000"
+        );
+    }
+
+    #[test]
+    fn test_html_dirs() {
+        let dir = env::temp_dir().join(format!("ng-diff-html-dirs-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let dir = dir.display().to_string();
+        match parse(&["--html", &dir, &dir]) {
+            Ok(Command::Diff(options)) => assert_eq!(run(&options), 2),
+            command => panic!("unexpected {:?}", command),
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,126 @@
+use crate::{diff_ops, format, hybrid_hirschberg_diff, Diff, DiffItem, DiffOp, DiffTag, Hirschberg, HirschbergAlg};
+
+/// The number of the leading bytes that are checked for NUL bytes, like in GNU diff
+pub const BINARY_CHECK_LEN: usize = 8000;
+
+/// Returns whether the content looks binary, which is whether it has a NUL byte
+/// among its leading `BINARY_CHECK_LEN` bytes or is not a valid UTF-8 text
+pub fn is_binary(content: &[u8]) -> bool {
+    as_text(content).is_none()
+}
+
+/// Returns the content as a text unless it looks binary
+pub fn as_text(content: &[u8]) -> Option<&str> {
+    if content.iter().take(BINARY_CHECK_LEN).any(|byte| *byte == 0) {
+        None
+    } else {
+        std::str::from_utf8(content).ok()
+    }
+}
+
+/// The result of `diff_lines`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LineDiff<'a> {
+    /// The lines, or the other items, of the texts and the runs of their diff
+    Lines {
+        first: Vec<&'a str>,
+        second: Vec<&'a str>,
+        ops: Vec<DiffOp>,
+    },
+    /// Either content is binary, so they are only compared as a whole
+    Binary { is_same: bool },
+}
+
+impl LineDiff<'_> {
+    pub fn is_same(&self) -> bool {
+        match self {
+            Self::Lines { ops, .. } => ops.iter().all(|op| op.tag == DiffTag::Equal),
+            Self::Binary { is_same } => *is_same,
+        }
+    }
+
+    /// Renders the diff in the normal format of GNU diff, the different
+    /// binary contents are reported as `Binary files FIRST and SECOND differ`
+    pub fn to_normal(&self, labels: (&str, &str)) -> String {
+        match self {
+            Self::Lines { first, second, ops } => format::normal(first, second, ops),
+            Self::Binary { is_same: true } => String::new(),
+            Self::Binary { is_same: false } => format::binary(labels),
+        }
+    }
+}
+
+/// Diffs the contents line by line unless either of them looks binary
+pub fn diff_lines<'a>(first: &'a [u8], second: &'a [u8]) -> LineDiff<'a> {
+    diff_contents(first, second, format::lines, Hirschberg::FULL_MATRIX_CELLS)
+}
+
+/// Diffs the contents by the items that `split` returns, e.g. `format::words`,
+/// unless either of them looks binary. The sub-problems below the
+/// `full_matrix_cells` are solved like by `HybridHirschberg`.
+pub fn diff_contents<'a>(
+    first: &'a [u8], second: &'a [u8], split: fn(&'a str) -> Vec<&'a str>, full_matrix_cells: usize,
+) -> LineDiff<'a> {
+    match (as_text(first), as_text(second)) {
+        (Some(first), Some(second)) => {
+            let (first, second) = (split(first), split(second));
+            let Diff(diff): Diff<Vec<DiffItem<_>>> =
+                hybrid_hirschberg_diff::<Vec<usize>, _, _, _>(first.iter(), second.iter(), full_matrix_cells);
+            let ops = diff_ops(&diff);
+            LineDiff::Lines { first, second, ops }
+        }
+        _ => LineDiff::Binary {
+            is_same: first == second,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DEFAULT_FULL_MATRIX_CELLS;
+
+    #[test]
+    fn test_is_binary() {
+        assert!(!is_binary(b"text\nlines\n"));
+        assert!(!is_binary(b""));
+        assert!(!is_binary("юникод\n".as_bytes()));
+        assert!(is_binary(b"\x7fELF\x02\x01\x01\x00"));
+        assert!(is_binary(b"latin-1 caf\xe9\n"));
+
+        let mut content = vec![b'a'; BINARY_CHECK_LEN];
+        content.push(0);
+        assert!(!is_binary(&content));
+    }
+
+    #[test]
+    fn test_diff_lines() {
+        let diff = diff_lines(b"a\nb\n", b"a\nc\n");
+        assert!(!diff.is_same());
+        assert_eq!(diff.to_normal(("x", "y")), "2c2\n< b\n---\n> c\n");
+
+        let diff = diff_lines(b"a\0b", b"a\0c");
+        assert_eq!(diff, LineDiff::Binary { is_same: false });
+        assert_eq!(diff.to_normal(("x", "y")), "Binary files x and y differ\n");
+
+        let diff = diff_lines(b"a\0b", b"a\0b");
+        assert!(diff.is_same());
+        assert_eq!(diff.to_normal(("x", "y")), "");
+    }
+
+    #[test]
+    fn test_diff_contents() {
+        let diff = diff_contents(b"one two", b"one three", format::words, DEFAULT_FULL_MATRIX_CELLS);
+        match &diff {
+            LineDiff::Lines { first, second, .. } => {
+                assert_eq!(first, &vec!["one", " ", "two"]);
+                assert_eq!(second, &vec!["one", " ", "three"]);
+            }
+            LineDiff::Binary { .. } => panic!("the texts are diffed as binary"),
+        }
+        assert!(!diff.is_same());
+
+        let diff = diff_contents(b"one\0", b"one\0", format::words, 0);
+        assert_eq!(diff, LineDiff::Binary { is_same: true });
+    }
+}
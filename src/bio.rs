@@ -0,0 +1,425 @@
+//! The alignment of biological sequences with the substitution matrices,
+//! and a minimal FASTA reader.
+//!
+//! ```
+//! use ng_diff::bio::{align_protein, Gap, BLOSUM62};
+//!
+//! let alignment = align_protein("HEAGAWGHEE", "PAWHEAE", &BLOSUM62, Gap::affine(-11, -1));
+//! assert_eq!(alignment.cigar.to_string(), "3D1X2=3X1=");
+//! ```
+
+use std::{
+    borrow::Borrow,
+    io::{self, BufRead},
+};
+
+use crate::{gotoh, Cigar, EditItem, Edits, Scoring};
+
+/// A table of the scores of aligning every pair of symbols of its alphabet
+#[derive(Debug)]
+pub struct SubstitutionMatrix {
+    pub name: &'static str,
+    alphabet: &'static [u8],
+    /// The symbol that stands for any symbol missing from the alphabet
+    fallback: u8,
+    scores: &'static [i8],
+}
+
+impl SubstitutionMatrix {
+    pub fn alphabet(&self) -> &'static [u8] {
+        self.alphabet
+    }
+
+    /// Returns the score of aligning `a` against `b`, the symbols are case-insensitive
+    pub fn score(&self, a: u8, b: u8) -> i8 {
+        let size = self.alphabet.len();
+        self.scores[self.index(a) * size + self.index(b)]
+    }
+
+    fn index(&self, symbol: u8) -> usize {
+        let symbol = symbol.to_ascii_uppercase();
+        self.alphabet
+            .iter()
+            .position(|&known| known == symbol)
+            .or_else(|| self.alphabet.iter().position(|&known| known == self.fallback))
+            .unwrap_or(0)
+    }
+}
+
+const PROTEIN_ALPHABET: &[u8] = b"ARNDCQEGHILKMFPSTWYVBZX*";
+
+/// The BLOSUM62 amino acid substitution matrix, as distributed by NCBI
+#[rustfmt::skip]
+pub static BLOSUM62: SubstitutionMatrix = SubstitutionMatrix {
+    name: "BLOSUM62",
+    alphabet: PROTEIN_ALPHABET,
+    fallback: b'X',
+    scores: &[
+    //   A   R   N   D   C   Q   E   G   H   I   L   K   M   F   P   S   T   W   Y   V   B   Z   X   *
+         4, -1, -2, -2,  0, -1, -1,  0, -2, -1, -1, -1, -1, -2, -1,  1,  0, -3, -2,  0, -2, -1,  0, -4, // A
+        -1,  5,  0, -2, -3,  1,  0, -2,  0, -3, -2,  2, -1, -3, -2, -1, -1, -3, -2, -3, -1,  0, -1, -4, // R
+        -2,  0,  6,  1, -3,  0,  0,  0,  1, -3, -3,  0, -2, -3, -2,  1,  0, -4, -2, -3,  3,  0, -1, -4, // N
+        -2, -2,  1,  6, -3,  0,  2, -1, -1, -3, -4, -1, -3, -3, -1,  0, -1, -4, -3, -3,  4,  1, -1, -4, // D
+         0, -3, -3, -3,  9, -3, -4, -3, -3, -1, -1, -3, -1, -2, -3, -1, -1, -2, -2, -1, -3, -3, -2, -4, // C
+        -1,  1,  0,  0, -3,  5,  2, -2,  0, -3, -2,  1,  0, -3, -1,  0, -1, -2, -1, -2,  0,  3, -1, -4, // Q
+        -1,  0,  0,  2, -4,  2,  5, -2,  0, -3, -3,  1, -2, -3, -1,  0, -1, -3, -2, -2,  1,  4, -1, -4, // E
+         0, -2,  0, -1, -3, -2, -2,  6, -2, -4, -4, -2, -3, -3, -2,  0, -2, -2, -3, -3, -1, -2, -1, -4, // G
+        -2,  0,  1, -1, -3,  0,  0, -2,  8, -3, -3, -1, -2, -1, -2, -1, -2, -2,  2, -3,  0,  0, -1, -4, // H
+        -1, -3, -3, -3, -1, -3, -3, -4, -3,  4,  2, -3,  1,  0, -3, -2, -1, -3, -1,  3, -3, -3, -1, -4, // I
+        -1, -2, -3, -4, -1, -2, -3, -4, -3,  2,  4, -2,  2,  0, -3, -2, -1, -2, -1,  1, -4, -3, -1, -4, // L
+        -1,  2,  0, -1, -3,  1,  1, -2, -1, -3, -2,  5, -1, -3, -1,  0, -1, -3, -2, -2,  0,  1, -1, -4, // K
+        -1, -1, -2, -3, -1,  0, -2, -3, -2,  1,  2, -1,  5,  0, -2, -1, -1, -1, -1,  1, -3, -1, -1, -4, // M
+        -2, -3, -3, -3, -2, -3, -3, -3, -1,  0,  0, -3,  0,  6, -4, -2, -2,  1,  3, -1, -3, -3, -1, -4, // F
+        -1, -2, -2, -1, -3, -1, -1, -2, -2, -3, -3, -1, -2, -4,  7, -1, -1, -4, -3, -2, -2, -1, -2, -4, // P
+         1, -1,  1,  0, -1,  0,  0,  0, -1, -2, -2,  0, -1, -2, -1,  4,  1, -3, -2, -2,  0,  0,  0, -4, // S
+         0, -1,  0, -1, -1, -1, -1, -2, -2, -1, -1, -1, -1, -2, -1,  1,  5, -2, -2,  0, -1, -1,  0, -4, // T
+        -3, -3, -4, -4, -2, -2, -3, -2, -2, -3, -2, -3, -1,  1, -4, -3, -2, 11,  2, -3, -4, -3, -2, -4, // W
+        -2, -2, -2, -3, -2, -1, -2, -3,  2, -1, -1, -2, -1,  3, -3, -2, -2,  2,  7, -1, -3, -2, -1, -4, // Y
+         0, -3, -3, -3, -1, -2, -2, -3, -3,  3,  1, -2,  1, -1, -2, -2,  0, -3, -1,  4, -3, -2, -1, -4, // V
+        -2, -1,  3,  4, -3,  0,  1, -1,  0, -3, -4,  0, -3, -3, -2,  0, -1, -4, -3, -3,  4,  1, -1, -4, // B
+        -1,  0,  0,  1, -3,  3,  4, -2,  0, -3, -3,  1, -1, -3, -1,  0, -1, -3, -2, -2,  1,  4, -1, -4, // Z
+         0, -1, -1, -1, -2, -1, -1, -1, -1, -1, -1, -1, -1, -1, -2,  0,  0, -2, -1, -1, -1, -1, -1, -4, // X
+        -4, -4, -4, -4, -4, -4, -4, -4, -4, -4, -4, -4, -4, -4, -4, -4, -4, -4, -4, -4, -4, -4, -4,  1, // *
+    ],
+};
+
+/// The PAM250 amino acid substitution matrix, as distributed by NCBI
+#[rustfmt::skip]
+pub static PAM250: SubstitutionMatrix = SubstitutionMatrix {
+    name: "PAM250",
+    alphabet: PROTEIN_ALPHABET,
+    fallback: b'X',
+    scores: &[
+    //   A   R   N   D   C   Q   E   G   H   I   L   K   M   F   P   S   T   W   Y   V   B   Z   X   *
+         2, -2,  0,  0, -2,  0,  0,  1, -1, -1, -2, -1, -1, -3,  1,  1,  1, -6, -3,  0,  0,  0,  0, -8, // A
+        -2,  6,  0, -1, -4,  1, -1, -3,  2, -2, -3,  3,  0, -4,  0,  0, -1,  2, -4, -2, -1,  0, -1, -8, // R
+         0,  0,  2,  2, -4,  1,  1,  0,  2, -2, -3,  1, -2, -3,  0,  1,  0, -4, -2, -2,  2,  1,  0, -8, // N
+         0, -1,  2,  4, -5,  2,  3,  1,  1, -2, -4,  0, -3, -6, -1,  0,  0, -7, -4, -2,  3,  3, -1, -8, // D
+        -2, -4, -4, -5, 12, -5, -5, -3, -3, -2, -6, -5, -5, -4, -3,  0, -2, -8,  0, -2, -4, -5, -3, -8, // C
+         0,  1,  1,  2, -5,  4,  2, -1,  3, -2, -2,  1, -1, -5,  0, -1, -1, -5, -4, -2,  1,  3, -1, -8, // Q
+         0, -1,  1,  3, -5,  2,  4,  0,  1, -2, -3,  0, -2, -5, -1,  0,  0, -7, -4, -2,  3,  3, -1, -8, // E
+         1, -3,  0,  1, -3, -1,  0,  5, -2, -3, -4, -2, -3, -5,  0,  1,  0, -7, -5, -1,  0,  0, -1, -8, // G
+        -1,  2,  2,  1, -3,  3,  1, -2,  6, -2, -2,  0, -2, -2,  0, -1, -1, -3,  0, -2,  1,  2, -1, -8, // H
+        -1, -2, -2, -2, -2, -2, -2, -3, -2,  5,  2, -2,  2,  1, -2, -1,  0, -5, -1,  4, -2, -2, -1, -8, // I
+        -2, -3, -3, -4, -6, -2, -3, -4, -2,  2,  6, -3,  4,  2, -3, -3, -2, -2, -1,  2, -3, -3, -1, -8, // L
+        -1,  3,  1,  0, -5,  1,  0, -2,  0, -2, -3,  5,  0, -5, -1,  0,  0, -3, -4, -2,  1,  0, -1, -8, // K
+        -1,  0, -2, -3, -5, -1, -2, -3, -2,  2,  4,  0,  6,  0, -2, -2, -1, -4, -2,  2, -2, -2, -1, -8, // M
+        -3, -4, -3, -6, -4, -5, -5, -5, -2,  1,  2, -5,  0,  9, -5, -3, -3,  0,  7, -1, -4, -5, -2, -8, // F
+         1,  0,  0, -1, -3,  0, -1,  0,  0, -2, -3, -1, -2, -5,  6,  1,  0, -6, -5, -1, -1,  0, -1, -8, // P
+         1,  0,  1,  0,  0, -1,  0,  1, -1, -1, -3,  0, -2, -3,  1,  2,  1, -2, -3, -1,  0,  0,  0, -8, // S
+         1, -1,  0,  0, -2, -1,  0,  0, -1,  0, -2,  0, -1, -3,  0,  1,  3, -5, -3,  0,  0, -1,  0, -8, // T
+        -6,  2, -4, -7, -8, -5, -7, -7, -3, -5, -2, -3, -4,  0, -6, -2, -5, 17,  0, -6, -5, -6, -4, -8, // W
+        -3, -4, -2, -4,  0, -4, -4, -5,  0, -1, -1, -4, -2,  7, -5, -3, -3,  0, 10, -2, -3, -4, -2, -8, // Y
+         0, -2, -2, -2, -2, -2, -2, -1, -2,  4,  2, -2,  2, -1, -1, -1,  0, -6, -2,  4, -2, -2, -1, -8, // V
+         0, -1,  2,  3, -4,  1,  3,  0,  1, -2, -3,  1, -2, -4, -1,  0,  0, -5, -3, -2,  3,  2, -1, -8, // B
+         0,  0,  1,  3, -5,  3,  3,  0,  2, -2, -3,  0, -2, -5,  0,  0, -1, -6, -4, -2,  2,  3, -1, -8, // Z
+         0, -1,  0, -1, -3, -1, -1, -1, -1, -1, -1, -1, -1, -2, -1,  0,  0, -4, -2, -1, -1, -1, -1, -8, // X
+        -8, -8, -8, -8, -8, -8, -8, -8, -8, -8, -8, -8, -8, -8, -8, -8, -8, -8, -8, -8, -8, -8, -8,  1, // *
+    ],
+};
+
+/// The nucleotide matrix with the identity and mismatch scores of NUC.4.4:
+/// +5 for the same bases, where U is the same as T, -4 for different bases,
+/// and -2 for the unknown base N against anything
+#[rustfmt::skip]
+pub static NUCLEOTIDE: SubstitutionMatrix = SubstitutionMatrix {
+    name: "NUCLEOTIDE",
+    alphabet: b"ACGTUN",
+    fallback: b'N',
+    scores: &[
+    //   A   C   G   T   U   N
+         5, -4, -4, -4, -4, -2, // A
+        -4,  5, -4, -4, -4, -2, // C
+        -4, -4,  5, -4, -4, -2, // G
+        -4, -4, -4,  5,  5, -2, // T
+        -4, -4, -4,  5,  5, -2, // U
+        -2, -2, -2, -2, -2, -2, // N
+    ],
+};
+
+/// The gap scores, where a gap of `k` symbols scores `open + k * extend`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Gap {
+    pub open: i64,
+    pub extend: i64,
+}
+
+impl Gap {
+    pub const fn linear(extend: i64) -> Self {
+        Self { open: 0, extend }
+    }
+
+    pub const fn affine(open: i64, extend: i64) -> Self {
+        Self { open, extend }
+    }
+}
+
+/// The scoring of the symbols by a substitution matrix
+#[derive(Clone, Copy, Debug)]
+pub struct MatrixScoring<'a> {
+    pub matrix: &'a SubstitutionMatrix,
+    pub gap: Gap,
+}
+
+impl<T: Borrow<u8>> Scoring<T> for MatrixScoring<'_> {
+    #[inline]
+    fn pair(&self, a: &T, b: &T) -> i64 {
+        self.matrix.score(*a.borrow(), *b.borrow()) as i64
+    }
+
+    #[inline]
+    fn gap(&self) -> i64 {
+        self.gap.extend
+    }
+
+    #[inline]
+    fn gap_open(&self) -> i64 {
+        self.gap.open
+    }
+}
+
+/// The global alignment of two biological sequences
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Alignment {
+    pub edits: Vec<EditItem<u8>>,
+    pub score: i64,
+    /// The CIGAR of the second sequence aligned against the first one
+    /// as the reference, with the `=`, `X`, `I` and `D` operations
+    pub cigar: Cigar,
+}
+
+impl Alignment {
+    /// Returns both sequences padded with the `-` gaps, so they are aligned by columns
+    pub fn gapped(&self) -> (String, String) {
+        let mut first = String::with_capacity(self.edits.len());
+        let mut second = String::with_capacity(self.edits.len());
+        for item in &self.edits {
+            let (a, b) = match *item {
+                EditItem::First(a) => (a, b'-'),
+                EditItem::Both(a) => (a, a),
+                EditItem::Second(b) => (b'-', b),
+                EditItem::Replace(a, b) => (a, b),
+            };
+            first.push(a as char);
+            second.push(b as char);
+        }
+        (first, second)
+    }
+}
+
+/// Aligns two protein sequences globally with the substitution `matrix`,
+/// e.g. `BLOSUM62` or `PAM250`
+pub fn align_protein(a: impl AsRef<[u8]>, b: impl AsRef<[u8]>, matrix: &SubstitutionMatrix, gap: Gap) -> Alignment {
+    align_with_matrix(a.as_ref(), b.as_ref(), matrix, gap)
+}
+
+/// Aligns two DNA or RNA sequences globally with the substitution `matrix`,
+/// e.g. `NUCLEOTIDE`
+pub fn align_nucleotide(a: impl AsRef<[u8]>, b: impl AsRef<[u8]>, matrix: &SubstitutionMatrix, gap: Gap) -> Alignment {
+    align_with_matrix(a.as_ref(), b.as_ref(), matrix, gap)
+}
+
+fn align_with_matrix(a: &[u8], b: &[u8], matrix: &SubstitutionMatrix, gap: Gap) -> Alignment {
+    let scoring = MatrixScoring { matrix, gap };
+    let Edits(edits): Edits<Vec<_>> = gotoh(a.iter().copied(), b.iter().copied(), &scoring);
+
+    let mut score = 0;
+    let mut prev_gap = None;
+    for item in &edits {
+        let item_gap = match item {
+            EditItem::First(_) => Some(true),
+            EditItem::Second(_) => Some(false),
+            EditItem::Both(_) | EditItem::Replace(..) => None,
+        };
+        score += match item {
+            EditItem::Both(x) => scoring.pair(x, x),
+            EditItem::Replace(x, y) => scoring.pair(x, y),
+            EditItem::First(_) | EditItem::Second(_) if item_gap == prev_gap => gap.extend,
+            EditItem::First(_) | EditItem::Second(_) => gap.open + gap.extend,
+        };
+        prev_gap = item_gap;
+    }
+
+    let cigar = Cigar::from(&edits[..]);
+    Alignment { edits, score, cigar }
+}
+
+/// A record of a FASTA file
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FastaRecord {
+    /// The first word of the header line
+    pub id: String,
+    /// The rest of the header line
+    pub description: String,
+    pub sequence: String,
+}
+
+/// A reader of the FASTA records, which skips the blank lines and the `;` comments
+#[derive(Debug)]
+pub struct FastaReader<R> {
+    reader: R,
+    header: Option<String>,
+    line: String,
+}
+
+impl<R: BufRead> FastaReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            header: None,
+            line: String::new(),
+        }
+    }
+
+    fn read_record(&mut self) -> io::Result<Option<FastaRecord>> {
+        let mut sequence = String::new();
+        loop {
+            self.line.clear();
+            let is_eof = self.reader.read_line(&mut self.line)? == 0;
+            let line = self.line.trim();
+
+            if is_eof || line.starts_with('>') {
+                let next_header = line.strip_prefix('>').map(str::to_string);
+                match std::mem::replace(&mut self.header, next_header) {
+                    Some(header) => {
+                        let mut header = header.trim().splitn(2, char::is_whitespace);
+                        return Ok(Some(FastaRecord {
+                            id: header.next().unwrap_or_default().to_string(),
+                            description: header.next().unwrap_or_default().trim().to_string(),
+                            sequence,
+                        }));
+                    }
+                    None if is_eof => return Ok(None),
+                    None => continue,
+                }
+            } else if line.is_empty() || line.starts_with(';') {
+                continue;
+            } else if self.header.is_none() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "the FASTA sequence has no header line",
+                ));
+            } else {
+                sequence.extend(line.chars().filter(|c| !c.is_whitespace()));
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for FastaReader<R> {
+    type Item = io::Result<FastaRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_record().transpose()
+    }
+}
+
+/// Reads all records of a FASTA text
+pub fn parse_fasta(text: &str) -> io::Result<Vec<FastaRecord>> {
+    FastaReader::new(text.as_bytes()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gotoh_score;
+
+    #[test]
+    fn test_substitution_matrices() {
+        for matrix in [&BLOSUM62, &PAM250, &NUCLEOTIDE] {
+            let size = matrix.alphabet().len();
+            assert_eq!(matrix.scores.len(), size * size, "{}", matrix.name);
+            for &a in matrix.alphabet() {
+                for &b in matrix.alphabet() {
+                    assert_eq!(matrix.score(a, b), matrix.score(b, a), "{}", matrix.name);
+                }
+            }
+        }
+
+        assert_eq!(BLOSUM62.score(b'W', b'W'), 11);
+        assert_eq!(BLOSUM62.score(b'c', b'C'), 9);
+        assert_eq!(BLOSUM62.score(b'I', b'V'), 3);
+        assert_eq!(BLOSUM62.score(b'J', b'A'), 0);
+        assert_eq!(PAM250.score(b'W', b'W'), 17);
+        assert_eq!(PAM250.score(b'F', b'Y'), 7);
+        assert_eq!(NUCLEOTIDE.score(b'T', b'u'), 5);
+        assert_eq!(NUCLEOTIDE.score(b'A', b'R'), -2);
+    }
+
+    #[test]
+    fn test_align_protein() {
+        let gap = Gap::affine(-11, -1);
+        let alignment = align_protein("HEAGAWGHEE", "PAWHEAE", &BLOSUM62, gap);
+        let scoring = MatrixScoring { matrix: &BLOSUM62, gap };
+        assert_eq!(
+            alignment.score,
+            gotoh_score(b"HEAGAWGHEE".iter(), b"PAWHEAE".iter(), &scoring)
+        );
+        assert_eq!(alignment.cigar.to_string(), "3D1X2=3X1=");
+
+        let alignment = align_protein("MKTAYIAK", "MKTAYIAK", &PAM250, Gap::linear(-8));
+        assert_eq!(alignment.cigar.to_string(), "8=");
+        assert_eq!(alignment.gapped(), ("MKTAYIAK".to_string(), "MKTAYIAK".to_string()));
+
+        let alignment = align_protein("", "", &BLOSUM62, gap);
+        assert_eq!(alignment.cigar.to_string(), "");
+        assert_eq!(alignment.score, 0);
+    }
+
+    #[test]
+    fn test_align_nucleotide() {
+        let alignment = align_nucleotide("ACGTTGCA", "ACGTGCA", &NUCLEOTIDE, Gap::affine(-10, -1));
+        assert_eq!(alignment.cigar.to_string(), "3=1D4=");
+        assert_eq!(alignment.score, 7 * 5 - 11);
+        assert_eq!(alignment.gapped(), ("ACGTTGCA".to_string(), "ACG-TGCA".to_string()));
+
+        let alignment = align_nucleotide("ACGU", "ACGT", &NUCLEOTIDE, Gap::linear(-4));
+        assert_eq!(alignment.cigar.to_string(), "3=1X");
+        assert_eq!(alignment.score, 20);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let alignment = align_nucleotide("AC", "AG", &NUCLEOTIDE, Gap::linear(-4));
+        let json = serde_json::to_string(&alignment).unwrap();
+        assert_eq!(
+            json,
+            r#"{"edits":[{"op":"equal","value":65},{"op":"replace","value":[67,71]}],"score":1,"cigar":"1=1X"}"#
+        );
+        assert_eq!(serde_json::from_str::<Alignment>(&json).unwrap(), alignment);
+    }
+
+    #[test]
+    fn test_fasta() {
+        let records = parse_fasta(
+            ">sp|P69905|HBA_HUMAN Hemoglobin subunit alpha\nMVLSPADKTNVKAAWGKVGAHAGEYGAEALER\nMFLSFPTTKTYFPHF\n\n; \
+             comment\n>seq2\nACGT\n>empty description \n",
+        )
+        .unwrap();
+        assert_eq!(
+            records,
+            vec![
+                FastaRecord {
+                    id: "sp|P69905|HBA_HUMAN".to_string(),
+                    description: "Hemoglobin subunit alpha".to_string(),
+                    sequence: "MVLSPADKTNVKAAWGKVGAHAGEYGAEALERMFLSFPTTKTYFPHF".to_string(),
+                },
+                FastaRecord {
+                    id: "seq2".to_string(),
+                    description: String::new(),
+                    sequence: "ACGT".to_string(),
+                },
+                FastaRecord {
+                    id: "empty".to_string(),
+                    description: "description".to_string(),
+                    sequence: String::new(),
+                },
+            ]
+        );
+
+        assert!(parse_fasta("").unwrap().is_empty());
+        assert!(parse_fasta("ACGT\n>seq\nACGT\n").is_err());
+    }
+}
@@ -1,23 +1,27 @@
 use std::iter::FusedIterator;
 
+use crate::{IndexIter, RandomAccessSeq};
+
 pub trait AsCharsIter {
-    fn chars_iter(&self) -> CharsIter;
+    fn chars_iter(&self) -> CharsIter<'_>;
 }
 
 impl AsCharsIter for str {
-    fn chars_iter(&self) -> CharsIter {
+    fn chars_iter(&self) -> CharsIter<'_> {
         self.into()
     }
 }
 
 impl AsCharsIter for String {
-    fn chars_iter(&self) -> CharsIter {
+    fn chars_iter(&self) -> CharsIter<'_> {
         self.into()
     }
 }
 
 /// An `ExactSizeIterator` implementation over the `char`s of
-/// a string slice.
+/// a string slice. Its `nth` and `nth_back` skip the ASCII text in O(1),
+/// so `hirschberg_diff` splits it in O(1) too, but decode the skipped chars
+/// of any other text, which is better pre-decoded into a `CharBuf`.
 #[derive(Debug, Clone)]
 pub struct CharsIter<'a> {
     chars: std::str::Chars<'a>,
@@ -49,6 +53,19 @@ impl Iterator for CharsIter<'_> {
     fn last(self) -> Option<char> {
         self.chars.last()
     }
+
+    #[inline]
+    fn nth(&mut self, n: usize) -> Option<char> {
+        if self.is_ascii() {
+            let rest = self.chars.as_str();
+            self.chars = rest[n.min(rest.len())..].chars();
+            self.len = self.chars.as_str().len();
+            self.next()
+        } else {
+            self.len = self.len.saturating_sub(n.saturating_add(1));
+            self.chars.nth(n)
+        }
+    }
 }
 
 impl DoubleEndedIterator for CharsIter<'_> {
@@ -59,6 +76,19 @@ impl DoubleEndedIterator for CharsIter<'_> {
         }
         self.chars.next_back()
     }
+
+    #[inline]
+    fn nth_back(&mut self, n: usize) -> Option<char> {
+        if self.is_ascii() {
+            let rest = self.chars.as_str();
+            self.chars = rest[..rest.len() - n.min(rest.len())].chars();
+            self.len = self.chars.as_str().len();
+            self.next_back()
+        } else {
+            self.len = self.len.saturating_sub(n.saturating_add(1));
+            self.chars.nth_back(n)
+        }
+    }
 }
 
 impl FusedIterator for CharsIter<'_> {}
@@ -77,6 +107,12 @@ impl<'a> CharsIter<'a> {
     pub fn as_str(&self) -> &'a str {
         self.chars.as_str()
     }
+
+    /// The rest of the text is ASCII when it has as many chars as bytes
+    #[inline]
+    fn is_ascii(&self) -> bool {
+        self.len == self.chars.as_str().len()
+    }
 }
 
 impl<'a> From<&'a str> for CharsIter<'a> {
@@ -102,3 +138,90 @@ impl<'a> From<&'a mut String> for CharsIter<'a> {
         Self::new(source.as_str())
     }
 }
+
+/// A buffer of the pre-decoded `char`s of a string, which gives O(1) access
+/// to them by index, unlike the `CharsIter`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CharBuf(Vec<char>);
+
+impl CharBuf {
+    #[inline]
+    pub fn new(source: &str) -> Self {
+        Self(source.chars().collect())
+    }
+
+    #[inline]
+    pub fn as_slice(&self) -> &[char] {
+        &self.0
+    }
+
+    #[inline]
+    pub fn iter(&self) -> IndexIter<&Self> {
+        self.index_iter()
+    }
+}
+
+impl RandomAccessSeq for &CharBuf {
+    type Item = char;
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    #[inline]
+    fn get(&self, index: usize) -> char {
+        self.0[index]
+    }
+}
+
+impl<'a> IntoIterator for &'a CharBuf {
+    type IntoIter = IndexIter<&'a CharBuf>;
+    type Item = char;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.index_iter()
+    }
+}
+
+impl From<&str> for CharBuf {
+    fn from(source: &str) -> Self {
+        Self::new(source)
+    }
+}
+
+impl From<&String> for CharBuf {
+    fn from(source: &String) -> Self {
+        Self::new(source.as_str())
+    }
+}
+
+impl From<Vec<char>> for CharBuf {
+    fn from(chars: Vec<char>) -> Self {
+        Self(chars)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nth() {
+        for text in ["abcdef", "aé😀bcd"] {
+            let chars: Vec<char> = text.chars().collect();
+            for n in 0..=chars.len() {
+                let mut iter = text.chars_iter();
+                assert_eq!(iter.nth(n), chars.get(n).copied(), "{:?}", text);
+                assert_eq!(iter.len(), chars.len().saturating_sub(n + 1));
+                assert_eq!(iter.collect::<Vec<_>>(), chars[(n + 1).min(chars.len())..]);
+
+                let mut iter = text.chars_iter();
+                let back = chars.len().checked_sub(n + 1).map(|index| chars[index]);
+                assert_eq!(iter.nth_back(n), back, "{:?}", text);
+                assert_eq!(iter.len(), chars.len().saturating_sub(n + 1));
+                assert_eq!(iter.as_str().chars().count(), iter.len());
+            }
+        }
+    }
+}
@@ -0,0 +1,426 @@
+//! The CIGAR strings of the SAM format, which encode an alignment as the runs
+//! of its operations, e.g. `3=1X2I4=`. The first sequence is the reference,
+//! so its items that are missing from the second one are the deletions.
+
+use std::{error::Error, fmt, str::FromStr};
+
+use crate::{DiffItem, Difference, EditItem, EditScript};
+
+/// An operation of the CIGAR string
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CigarOp {
+    /// `M`, an aligned pair of items that may be equal or not
+    Match,
+    /// `=`, an aligned pair of equal items
+    Equal,
+    /// `X`, an aligned pair of different items
+    Mismatch,
+    /// `I`, an item of the second sequence only
+    Insertion,
+    /// `D`, an item of the first sequence only
+    Deletion,
+}
+
+impl CigarOp {
+    pub fn from_char(op: char) -> Option<Self> {
+        match op {
+            'M' => Some(Self::Match),
+            '=' => Some(Self::Equal),
+            'X' => Some(Self::Mismatch),
+            'I' => Some(Self::Insertion),
+            'D' => Some(Self::Deletion),
+            _ => None,
+        }
+    }
+
+    pub fn as_char(self) -> char {
+        match self {
+            Self::Match => 'M',
+            Self::Equal => '=',
+            Self::Mismatch => 'X',
+            Self::Insertion => 'I',
+            Self::Deletion => 'D',
+        }
+    }
+
+    /// Returns whether the operation consumes the items of the first
+    /// and the second sequences
+    pub fn consumes(self) -> (bool, bool) {
+        match self {
+            Self::Match | Self::Equal | Self::Mismatch => (true, true),
+            Self::Insertion => (false, true),
+            Self::Deletion => (true, false),
+        }
+    }
+}
+
+impl fmt::Display for CigarOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_char())
+    }
+}
+
+/// The run-length encoded operations of an alignment. It is a `Difference`
+/// itself, so the diff functions can return it directly.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Cigar {
+    runs: Vec<(usize, CigarOp)>,
+}
+
+impl Cigar {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the runs of the operations with their lengths
+    pub fn runs(&self) -> &[(usize, CigarOp)] {
+        &self.runs
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.runs.is_empty()
+    }
+
+    /// Appends `count` operations, merging them into the last run of the same operation
+    pub fn push(&mut self, op: CigarOp, count: usize) {
+        if count == 0 {
+            return;
+        }
+        match self.runs.last_mut() {
+            Some((run_count, run_op)) if *run_op == op => *run_count += count,
+            _ => self.runs.push((count, op)),
+        }
+    }
+
+    /// Returns the number of the items of the first and the second sequences
+    /// covered by the operations
+    pub fn lens(&self) -> (usize, usize) {
+        self.runs.iter().fold((0, 0), |(first, second), &(count, op)| {
+            let (in_first, in_second) = op.consumes();
+            (
+                first + if in_first { count } else { 0 },
+                second + if in_second { count } else { 0 },
+            )
+        })
+    }
+
+    /// Returns the CIGAR with the `=` and `X` operations merged into `M`,
+    /// as the older tools expect
+    pub fn to_matches(&self) -> Self {
+        let mut cigar = Self::new();
+        for &(count, op) in &self.runs {
+            let op = match op {
+                CigarOp::Equal | CigarOp::Mismatch => CigarOp::Match,
+                op => op,
+            };
+            cigar.push(op, count);
+        }
+        cigar
+    }
+
+    /// Restores the edit script of the sequences `a` and `b` described
+    /// by the CIGAR. The `M` pairs are compared to tell the equal items from
+    /// the replaced ones, and the `=` and `X` pairs are checked to be so.
+    pub fn to_edits<SeqA, SeqB, SeqC>(&self, a: SeqA, b: SeqB) -> Result<SeqC, CigarError>
+    where
+        SeqA: IntoIterator,
+        SeqB: IntoIterator<Item = SeqA::Item>,
+        SeqA::Item: PartialEq,
+        SeqC: EditScript<SeqA::Item>,
+    {
+        let (mut a, mut b) = (a.into_iter(), b.into_iter());
+        let (mut i, mut j) = (0, 0);
+        let mut edits = SeqC::empty();
+
+        for &(count, op) in &self.runs {
+            for _ in 0..count {
+                match op {
+                    CigarOp::Deletion => {
+                        edits.push_first(a.next().ok_or(CigarError::TooShort)?);
+                        i += 1;
+                    }
+                    CigarOp::Insertion => {
+                        edits.push_second(b.next().ok_or(CigarError::TooShort)?);
+                        j += 1;
+                    }
+                    CigarOp::Match | CigarOp::Equal | CigarOp::Mismatch => {
+                        let (x, y) = (
+                            a.next().ok_or(CigarError::TooShort)?,
+                            b.next().ok_or(CigarError::TooShort)?,
+                        );
+                        match (op, x == y) {
+                            (CigarOp::Equal, false) | (CigarOp::Mismatch, true) => {
+                                return Err(CigarError::Mismatch { first: i, second: j });
+                            }
+                            (_, true) => edits.push_both(x),
+                            (_, false) => edits.push_replace(x, y),
+                        }
+                        i += 1;
+                        j += 1;
+                    }
+                }
+            }
+        }
+
+        if a.next().is_some() || b.next().is_some() {
+            return Err(CigarError::TooLong);
+        }
+        Ok(edits)
+    }
+}
+
+impl<T> Difference<T> for Cigar {
+    fn empty() -> Self {
+        Self::new()
+    }
+
+    fn push_first(&mut self, _item: T) {
+        self.push(CigarOp::Deletion, 1)
+    }
+
+    fn push_both(&mut self, _item: T) {
+        self.push(CigarOp::Equal, 1)
+    }
+
+    fn push_second(&mut self, _item: T) {
+        self.push(CigarOp::Insertion, 1)
+    }
+}
+
+impl<T> EditScript<T> for Cigar {
+    fn push_replace(&mut self, _first: T, _second: T) {
+        self.push(CigarOp::Mismatch, 1)
+    }
+}
+
+impl<T> From<&[DiffItem<T>]> for Cigar {
+    fn from(items: &[DiffItem<T>]) -> Self {
+        let mut cigar = Self::new();
+        for item in items {
+            let op = match item {
+                DiffItem::First(_) => CigarOp::Deletion,
+                DiffItem::Both(_) => CigarOp::Equal,
+                DiffItem::Second(_) => CigarOp::Insertion,
+            };
+            cigar.push(op, 1);
+        }
+        cigar
+    }
+}
+
+impl<T> From<&[EditItem<T>]> for Cigar {
+    fn from(items: &[EditItem<T>]) -> Self {
+        let mut cigar = Self::new();
+        for item in items {
+            let op = match item {
+                EditItem::First(_) => CigarOp::Deletion,
+                EditItem::Both(_) => CigarOp::Equal,
+                EditItem::Second(_) => CigarOp::Insertion,
+                EditItem::Replace(..) => CigarOp::Mismatch,
+            };
+            cigar.push(op, 1);
+        }
+        cigar
+    }
+}
+
+impl fmt::Display for Cigar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (count, op) in &self.runs {
+            write!(f, "{}{}", count, op)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Cigar {
+    type Err = CigarError;
+
+    /// Parses a CIGAR string, the `*` stands for the empty one
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut cigar = Self::new();
+        if s == "*" {
+            return Ok(cigar);
+        }
+
+        let mut count: Option<usize> = None;
+        for (position, c) in s.char_indices() {
+            if let Some(digit) = c.to_digit(10) {
+                let value = count
+                    .unwrap_or(0)
+                    .checked_mul(10)
+                    .and_then(|value| value.checked_add(digit as usize))
+                    .ok_or(CigarError::Overflow { position })?;
+                count = Some(value);
+            } else {
+                let op = CigarOp::from_char(c).ok_or(CigarError::InvalidOp { op: c, position })?;
+                match count.take() {
+                    Some(count) if count > 0 => cigar.push(op, count),
+                    _ => return Err(CigarError::MissingCount { position }),
+                }
+            }
+        }
+        if count.is_some() {
+            return Err(CigarError::MissingOp);
+        }
+        Ok(cigar)
+    }
+}
+
+/// The error of parsing a CIGAR string or applying it to the sequences
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CigarError {
+    /// An unknown operation at the byte position of the string
+    InvalidOp { op: char, position: usize },
+    /// An operation at the byte position has no positive count before it
+    MissingCount { position: usize },
+    /// The string ends with a count without an operation
+    MissingOp,
+    /// The count ending at the byte position does not fit into `usize`
+    Overflow { position: usize },
+    /// The sequences end before the operations do
+    TooShort,
+    /// The sequences have items left after the operations
+    TooLong,
+    /// The `=` pair of different items or the `X` pair of equal items
+    /// at the positions of the sequences
+    Mismatch { first: usize, second: usize },
+}
+
+impl fmt::Display for CigarError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidOp { op, position } => write!(f, "invalid CIGAR operation '{}' at {}", op, position),
+            Self::MissingCount { position } => write!(f, "missing CIGAR operation count at {}", position),
+            Self::MissingOp => write!(f, "missing CIGAR operation at the end"),
+            Self::Overflow { position } => write!(f, "too large CIGAR operation count at {}", position),
+            Self::TooShort => write!(f, "the sequences are shorter than the CIGAR"),
+            Self::TooLong => write!(f, "the sequences are longer than the CIGAR"),
+            Self::Mismatch { first, second } => {
+                write!(f, "the CIGAR does not match the items at {} and {}", first, second)
+            }
+        }
+    }
+}
+
+impl Error for CigarError {}
+
+/// Serializes the CIGAR as its string, e.g. `"3=1X2I"`
+#[cfg(feature = "serde")]
+impl serde::Serialize for Cigar {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Cigar {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let cigar = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        cigar.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Serializes the operation as its char, e.g. `"="`
+#[cfg(feature = "serde")]
+impl serde::Serialize for CigarOp {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_char(self.as_char())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for CigarOp {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let op = char::deserialize(deserializer)?;
+        Self::from_char(op).ok_or_else(|| serde::de::Error::custom(format!("invalid CIGAR operation '{}'", op)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{hirschberg_diff, AsCharsIter, Diff, Edits};
+
+    #[test]
+    fn test_cigar_parse() {
+        let cigar: Cigar = "3=1X2I4=1D".parse().unwrap();
+        assert_eq!(
+            cigar.runs(),
+            &[
+                (3, CigarOp::Equal),
+                (1, CigarOp::Mismatch),
+                (2, CigarOp::Insertion),
+                (4, CigarOp::Equal),
+                (1, CigarOp::Deletion),
+            ]
+        );
+        assert_eq!(cigar.to_string(), "3=1X2I4=1D");
+        assert_eq!(cigar.lens(), (9, 10));
+        assert_eq!(cigar.to_matches().to_string(), "4M2I4M1D");
+
+        assert_eq!("*".parse(), Ok(Cigar::new()));
+        assert_eq!("".parse(), Ok(Cigar::new()));
+        assert_eq!("10M".parse::<Cigar>().map(|cigar| cigar.lens()), Ok((10, 10)));
+        assert_eq!("2M3M".parse::<Cigar>().unwrap().runs(), &[(5, CigarOp::Match)]);
+
+        assert_eq!(
+            "3=2S".parse::<Cigar>(),
+            Err(CigarError::InvalidOp { op: 'S', position: 3 })
+        );
+        assert_eq!("3=M".parse::<Cigar>(), Err(CigarError::MissingCount { position: 2 }));
+        assert_eq!("0M".parse::<Cigar>(), Err(CigarError::MissingCount { position: 1 }));
+        assert_eq!("3=4".parse::<Cigar>(), Err(CigarError::MissingOp));
+        assert_eq!(
+            "99999999999999999999M".parse::<Cigar>(),
+            Err(CigarError::Overflow { position: 19 })
+        );
+    }
+
+    #[test]
+    fn test_cigar_diff() {
+        let cigar: Cigar = hirschberg_diff::<Vec<usize>, _, _, _>("ACGTTGCA".chars_iter(), "ACGTGCAA".chars_iter());
+        assert_eq!(cigar.to_string(), "3=1D4=1I");
+
+        let Diff(diff): Diff<Vec<_>> =
+            hirschberg_diff::<Vec<usize>, _, _, _>("ACGTTGCA".chars_iter(), "ACGTGCAA".chars_iter());
+        assert_eq!(Cigar::from(diff.as_slice()), cigar);
+
+        let Diff(restored): Diff<Vec<_>> = cigar.to_edits("ACGTTGCA".chars(), "ACGTGCAA".chars()).unwrap();
+        assert_eq!(restored, diff);
+
+        let cigar: Cigar = "2M1I1M".parse().unwrap();
+        let Edits(edits): Edits<Vec<_>> = cigar.to_edits("ACT".chars(), "AGCA".chars()).unwrap();
+        assert_eq!(
+            edits,
+            vec![
+                EditItem::Both('A'),
+                EditItem::Replace('C', 'G'),
+                EditItem::Second('C'),
+                EditItem::Replace('T', 'A'),
+            ]
+        );
+        assert_eq!(Cigar::from(edits.as_slice()).to_string(), "1=1X1I1X");
+
+        let result: Result<Edits<Vec<_>>, _> = cigar.to_edits("AC".chars(), "AGCA".chars());
+        assert_eq!(result.err(), Some(CigarError::TooShort));
+        let result: Result<Edits<Vec<_>>, _> = cigar.to_edits("ACTT".chars(), "AGCA".chars());
+        assert_eq!(result.err(), Some(CigarError::TooLong));
+        let result: Result<Edits<Vec<_>>, _> = "3=1I".parse::<Cigar>().unwrap().to_edits("ACT".chars(), "AGCA".chars());
+        assert_eq!(result.err(), Some(CigarError::Mismatch { first: 1, second: 1 }));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let cigar: Cigar = "3=1X2I".parse().unwrap();
+        assert_eq!(serde_json::to_string(&cigar).unwrap(), r#""3=1X2I""#);
+        assert_eq!(serde_json::from_str::<Cigar>(r#""3=1X2I""#).unwrap(), cigar);
+        assert_eq!(serde_json::from_str::<Cigar>(r#""""#).unwrap(), Cigar::new());
+        assert!(serde_json::from_str::<Cigar>(r#""3Q""#).is_err());
+
+        assert_eq!(serde_json::to_string(&CigarOp::Insertion).unwrap(), r#""I""#);
+        assert_eq!(serde_json::from_str::<CigarOp>(r#""D""#).unwrap(), CigarOp::Deletion);
+        assert!(serde_json::from_str::<CigarOp>(r#""Q""#).is_err());
+    }
+}
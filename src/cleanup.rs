@@ -0,0 +1,609 @@
+//! The post-processing of diffs that trades the minimality for readability,
+//! ported from the `diff_cleanupSemantic`, `diff_cleanupSemanticLossless`
+//! and `diff_cleanupEfficiency` of Neil Fraser's diff-match-patch.
+//!
+//! A minimal character diff often matches single common letters inside
+//! otherwise rewritten words. The semantic cleanup eliminates the equalities
+//! that are not longer than the edits on both sides of them, and shifts
+//! the remaining edits to the word and line boundaries. The efficiency cleanup
+//! eliminates the short equalities whose cost of keeping is higher than
+//! the `edit_cost` of an additional edit.
+
+use std::mem;
+
+use crate::DiffItem;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Op {
+    Delete,
+    Equal,
+    Insert,
+}
+
+type Run<T> = (Op, Vec<T>);
+
+/// Eliminates the semantically trivial equalities, then shifts the edits
+/// to the word boundaries and factors out the overlaps of the deletions
+/// and the insertions
+pub fn cleanup_semantic(diff: Vec<DiffItem<char>>) -> Vec<DiffItem<char>> {
+    let mut runs = into_runs(diff);
+    eliminate_semantic(&mut runs);
+    shift_lossless(&mut runs);
+    eliminate_overlaps(&mut runs);
+    from_runs(runs)
+}
+
+/// Shifts the single edits surrounded by the equalities to the word
+/// and line boundaries, keeping the diff minimal
+pub fn cleanup_semantic_lossless(diff: Vec<DiffItem<char>>) -> Vec<DiffItem<char>> {
+    let mut runs = into_runs(diff);
+    shift_lossless(&mut runs);
+    from_runs(runs)
+}
+
+/// Eliminates the equalities shorter than the `edit_cost` that are
+/// surrounded by the edits on all four sides, or shorter than a half
+/// of the `edit_cost` and surrounded by the edits on three sides
+pub fn cleanup_efficiency<T: Clone + PartialEq>(diff: Vec<DiffItem<T>>, edit_cost: usize) -> Vec<DiffItem<T>> {
+    let mut runs = into_runs(diff);
+    let mut changes = false;
+    let mut equalities: Vec<usize> = Vec::new();
+    let mut last_equality: Option<usize> = None;
+    let (mut pre_insert, mut pre_delete, mut post_insert, mut post_delete) = (false, false, false, false);
+
+    let mut pointer = 0;
+    while pointer < runs.len() {
+        if runs[pointer].0 == Op::Equal {
+            if runs[pointer].1.len() < edit_cost && (post_insert || post_delete) {
+                equalities.push(pointer);
+                pre_insert = post_insert;
+                pre_delete = post_delete;
+                last_equality = Some(runs[pointer].1.len());
+            } else {
+                equalities.clear();
+                last_equality = None;
+            }
+            post_insert = false;
+            post_delete = false;
+        } else {
+            if runs[pointer].0 == Op::Delete {
+                post_delete = true;
+            } else {
+                post_insert = true;
+            }
+
+            let sides = [pre_insert, pre_delete, post_insert, post_delete]
+                .iter()
+                .filter(|side| **side)
+                .count();
+            let is_eliminated = match last_equality {
+                Some(len) => sides == 4 || (len * 2 < edit_cost && sides == 3),
+                None => false,
+            };
+            if is_eliminated {
+                let index = equalities.pop().unwrap_or_default();
+                let equality = runs[index].1.clone();
+                runs.insert(index, (Op::Delete, equality));
+                runs[index + 1].0 = Op::Insert;
+                last_equality = None;
+                changes = true;
+
+                if pre_insert && pre_delete {
+                    post_insert = true;
+                    post_delete = true;
+                    equalities.clear();
+                } else {
+                    equalities.pop();
+                    post_insert = false;
+                    post_delete = false;
+                    match equalities.last() {
+                        Some(&index) => pointer = index,
+                        None => {
+                            pointer = 0;
+                            continue;
+                        }
+                    }
+                }
+            }
+        }
+        pointer += 1;
+    }
+
+    if changes {
+        merge(&mut runs);
+    }
+    from_runs(runs)
+}
+
+fn into_runs<T>(diff: Vec<DiffItem<T>>) -> Vec<Run<T>> {
+    let mut runs: Vec<Run<T>> = Vec::new();
+    for item in diff {
+        let (op, item) = match item {
+            DiffItem::First(x) => (Op::Delete, x),
+            DiffItem::Both(x) => (Op::Equal, x),
+            DiffItem::Second(x) => (Op::Insert, x),
+        };
+        match runs.last_mut() {
+            Some((run_op, run)) if *run_op == op => run.push(item),
+            _ => runs.push((op, vec![item])),
+        }
+    }
+    runs
+}
+
+fn from_runs<T>(runs: Vec<Run<T>>) -> Vec<DiffItem<T>> {
+    let mut diff = Vec::new();
+    for (op, run) in runs {
+        diff.extend(run.into_iter().map(|item| match op {
+            Op::Delete => DiffItem::First(item),
+            Op::Equal => DiffItem::Both(item),
+            Op::Insert => DiffItem::Second(item),
+        }));
+    }
+    diff
+}
+
+fn common_prefix<T: PartialEq>(a: &[T], b: &[T]) -> usize {
+    a.iter().zip(b).take_while(|(a, b)| a == b).count()
+}
+
+fn common_suffix<T: PartialEq>(a: &[T], b: &[T]) -> usize {
+    a.iter().rev().zip(b.iter().rev()).take_while(|(a, b)| a == b).count()
+}
+
+/// Returns the length of the longest suffix of `a` that is a prefix of `b`
+fn common_overlap<T: PartialEq>(a: &[T], b: &[T]) -> usize {
+    (1..=a.len().min(b.len()))
+        .rev()
+        .find(|&len| a[a.len() - len..] == b[..len])
+        .unwrap_or(0)
+}
+
+/// Eliminates the equalities that are not longer than the edits
+/// before and after them
+fn eliminate_semantic<T: Clone + PartialEq>(runs: &mut Vec<Run<T>>) {
+    let mut changes = false;
+    let mut equalities: Vec<usize> = Vec::new();
+    let mut last_equality: Option<usize> = None;
+    // The lengths of the insertions and deletions before and after the last equality
+    let (mut inserted_before, mut deleted_before, mut inserted_after, mut deleted_after) = (0, 0, 0, 0);
+
+    let mut pointer = 0;
+    while pointer < runs.len() {
+        if runs[pointer].0 == Op::Equal {
+            equalities.push(pointer);
+            inserted_before = inserted_after;
+            deleted_before = deleted_after;
+            inserted_after = 0;
+            deleted_after = 0;
+            last_equality = Some(runs[pointer].1.len());
+        } else {
+            if runs[pointer].0 == Op::Insert {
+                inserted_after += runs[pointer].1.len();
+            } else {
+                deleted_after += runs[pointer].1.len();
+            }
+
+            let is_eliminated = match last_equality {
+                Some(len) => len <= inserted_before.max(deleted_before) && len <= inserted_after.max(deleted_after),
+                None => false,
+            };
+            if is_eliminated {
+                let index = equalities.pop().unwrap_or_default();
+                let equality = runs[index].1.clone();
+                runs.insert(index, (Op::Delete, equality));
+                runs[index + 1].0 = Op::Insert;
+                equalities.pop();
+
+                inserted_before = 0;
+                deleted_before = 0;
+                inserted_after = 0;
+                deleted_after = 0;
+                last_equality = None;
+                changes = true;
+                match equalities.last() {
+                    Some(&index) => pointer = index,
+                    None => {
+                        pointer = 0;
+                        continue;
+                    }
+                }
+            }
+        }
+        pointer += 1;
+    }
+
+    if changes {
+        merge(runs);
+    }
+}
+
+/// Splits the overlaps of the adjacent deletions and insertions into equalities,
+/// if the overlap is at least as long as a half of either of them
+fn eliminate_overlaps<T: Clone + PartialEq>(runs: &mut Vec<Run<T>>) {
+    let mut pointer = 1;
+    while pointer < runs.len() {
+        if runs[pointer - 1].0 == Op::Delete && runs[pointer].0 == Op::Insert {
+            let deletion = runs[pointer - 1].1.clone();
+            let insertion = runs[pointer].1.clone();
+            let overlap = common_overlap(&deletion, &insertion);
+            let reverse_overlap = common_overlap(&insertion, &deletion);
+
+            if overlap >= reverse_overlap {
+                if overlap * 2 >= deletion.len() || overlap * 2 >= insertion.len() {
+                    runs.insert(pointer, (Op::Equal, insertion[..overlap].to_vec()));
+                    runs[pointer - 1].1 = deletion[..deletion.len() - overlap].to_vec();
+                    runs[pointer + 1].1 = insertion[overlap..].to_vec();
+                    pointer += 1;
+                }
+            } else if reverse_overlap * 2 >= deletion.len() || reverse_overlap * 2 >= insertion.len() {
+                runs.insert(pointer, (Op::Equal, deletion[..reverse_overlap].to_vec()));
+                runs[pointer - 1] = (Op::Insert, insertion[..insertion.len() - reverse_overlap].to_vec());
+                runs[pointer + 1] = (Op::Delete, deletion[reverse_overlap..].to_vec());
+                pointer += 1;
+            }
+            pointer += 1;
+        }
+        pointer += 1;
+    }
+    runs.retain(|(_, run)| !run.is_empty());
+}
+
+/// Slides the single edits surrounded by the equalities to the position
+/// with the best boundary score
+fn shift_lossless(runs: &mut Vec<Run<char>>) {
+    let mut pointer = 1;
+    while pointer + 1 < runs.len() {
+        if runs[pointer - 1].0 == Op::Equal && runs[pointer + 1].0 == Op::Equal {
+            let mut equality1 = runs[pointer - 1].1.clone();
+            let mut edit = runs[pointer].1.clone();
+            let mut equality2 = runs[pointer + 1].1.clone();
+
+            // Shift the edit as far left as possible
+            let offset = common_suffix(&equality1, &edit);
+            if offset > 0 {
+                let common = edit[edit.len() - offset..].to_vec();
+                equality1.truncate(equality1.len() - offset);
+                edit = [&common[..], &edit[..edit.len() - offset]].concat();
+                equality2 = [&common[..], &equality2[..]].concat();
+            }
+
+            // Step right, looking for the best fit
+            let mut best = (equality1.clone(), edit.clone(), equality2.clone());
+            let mut best_score = boundary_score(&equality1, &edit) + boundary_score(&edit, &equality2);
+            while !edit.is_empty() && !equality2.is_empty() && edit[0] == equality2[0] {
+                equality1.push(edit.remove(0));
+                edit.push(equality2.remove(0));
+                let score = boundary_score(&equality1, &edit) + boundary_score(&edit, &equality2);
+                // The `>=` encourages trailing rather than leading whitespace on the edits
+                if score >= best_score {
+                    best_score = score;
+                    best = (equality1.clone(), edit.clone(), equality2.clone());
+                }
+            }
+
+            if runs[pointer - 1].1 != best.0 {
+                let (best_equality1, best_edit, best_equality2) = best;
+                runs[pointer].1 = best_edit;
+                if best_equality2.is_empty() {
+                    runs.remove(pointer + 1);
+                } else {
+                    runs[pointer + 1].1 = best_equality2;
+                }
+                if best_equality1.is_empty() {
+                    runs.remove(pointer - 1);
+                    pointer -= 1;
+                } else {
+                    runs[pointer - 1].1 = best_equality1;
+                }
+            }
+        }
+        pointer += 1;
+    }
+}
+
+/// Scores the boundary between `one` and `two` from 6 (the best)
+/// to 0 (the worst): the edges, the blank lines, the line breaks,
+/// the ends of the sentences, the whitespace, the punctuation
+fn boundary_score(one: &[char], two: &[char]) -> u8 {
+    let (char1, char2) = match (one.last(), two.first()) {
+        (Some(&char1), Some(&char2)) => (char1, char2),
+        _ => return 6,
+    };
+
+    let non_alphanumeric1 = !char1.is_alphanumeric();
+    let non_alphanumeric2 = !char2.is_alphanumeric();
+    let whitespace1 = non_alphanumeric1 && char1.is_whitespace();
+    let whitespace2 = non_alphanumeric2 && char2.is_whitespace();
+    let line_break1 = whitespace1 && (char1 == '\r' || char1 == '\n');
+    let line_break2 = whitespace2 && (char2 == '\r' || char2 == '\n');
+    let blank_line1 = line_break1 && (one.ends_with(&['\n', '\n']) || one.ends_with(&['\n', '\r', '\n']));
+    let blank_line2 = line_break2
+        && [
+            &['\n', '\n'][..],
+            &['\n', '\r', '\n'],
+            &['\r', '\n', '\n'],
+            &['\r', '\n', '\r', '\n'],
+        ]
+        .iter()
+        .any(|prefix| two.starts_with(prefix));
+
+    if blank_line1 || blank_line2 {
+        5
+    } else if line_break1 || line_break2 {
+        4
+    } else if non_alphanumeric1 && !whitespace1 && whitespace2 {
+        3
+    } else if whitespace1 || whitespace2 {
+        2
+    } else if non_alphanumeric1 || non_alphanumeric2 {
+        1
+    } else {
+        0
+    }
+}
+
+/// Merges the adjacent runs of the same operation, factors out the common
+/// prefixes and suffixes of the deletions and insertions between the equalities,
+/// and shifts the single edits to eliminate the equalities
+fn merge<T: Clone + PartialEq>(runs: &mut Vec<Run<T>>) {
+    runs.push((Op::Equal, Vec::new()));
+    let (mut deleted_runs, mut inserted_runs) = (0, 0);
+    let (mut deleted, mut inserted): (Vec<T>, Vec<T>) = (Vec::new(), Vec::new());
+
+    let mut pointer = 0;
+    while pointer < runs.len() {
+        match runs[pointer].0 {
+            Op::Insert => {
+                inserted_runs += 1;
+                inserted.extend_from_slice(&runs[pointer].1);
+                pointer += 1;
+            }
+            Op::Delete => {
+                deleted_runs += 1;
+                deleted.extend_from_slice(&runs[pointer].1);
+                pointer += 1;
+            }
+            Op::Equal => {
+                if deleted_runs + inserted_runs > 1 {
+                    if deleted_runs != 0 && inserted_runs != 0 {
+                        let prefix = common_prefix(&inserted, &deleted);
+                        if prefix != 0 {
+                            let index = pointer - deleted_runs - inserted_runs;
+                            if index > 0 && runs[index - 1].0 == Op::Equal {
+                                runs[index - 1].1.extend_from_slice(&inserted[..prefix]);
+                            } else {
+                                runs.insert(0, (Op::Equal, inserted[..prefix].to_vec()));
+                                pointer += 1;
+                            }
+                            inserted.drain(..prefix);
+                            deleted.drain(..prefix);
+                        }
+
+                        let suffix = common_suffix(&inserted, &deleted);
+                        if suffix != 0 {
+                            let equality = mem::take(&mut runs[pointer].1);
+                            runs[pointer].1 = [&inserted[inserted.len() - suffix..], &equality[..]].concat();
+                            inserted.truncate(inserted.len() - suffix);
+                            deleted.truncate(deleted.len() - suffix);
+                        }
+                    }
+
+                    pointer -= deleted_runs + inserted_runs;
+                    runs.drain(pointer..pointer + deleted_runs + inserted_runs);
+                    if !deleted.is_empty() {
+                        runs.insert(pointer, (Op::Delete, mem::take(&mut deleted)));
+                        pointer += 1;
+                    }
+                    if !inserted.is_empty() {
+                        runs.insert(pointer, (Op::Insert, mem::take(&mut inserted)));
+                        pointer += 1;
+                    }
+                    pointer += 1;
+                } else if pointer != 0 && runs[pointer - 1].0 == Op::Equal {
+                    let (_, equality) = runs.remove(pointer);
+                    runs[pointer - 1].1.extend(equality);
+                } else {
+                    pointer += 1;
+                }
+                deleted_runs = 0;
+                inserted_runs = 0;
+                deleted.clear();
+                inserted.clear();
+            }
+        }
+    }
+    if runs.last().is_some_and(|(_, run)| run.is_empty()) {
+        runs.pop();
+    }
+
+    // Shift the single edits surrounded by the equalities sideways
+    // to eliminate an equality, e.g. A<ins>BA</ins>C -> <ins>AB</ins>AC
+    let mut changes = false;
+    let mut pointer = 1;
+    while pointer + 1 < runs.len() {
+        if runs[pointer - 1].0 == Op::Equal && runs[pointer + 1].0 == Op::Equal {
+            let (previous, edit, next) = (&runs[pointer - 1].1, &runs[pointer].1, &runs[pointer + 1].1);
+            if edit.ends_with(previous) {
+                let edit = [&previous[..], &edit[..edit.len() - previous.len()]].concat();
+                let next = [&previous[..], &next[..]].concat();
+                runs[pointer].1 = edit;
+                runs[pointer + 1].1 = next;
+                runs.remove(pointer - 1);
+                changes = true;
+            } else if edit.starts_with(next) {
+                let edit = [&edit[next.len()..], &next[..]].concat();
+                let (_, next) = runs.remove(pointer + 1);
+                runs[pointer - 1].1.extend(next);
+                runs[pointer].1 = edit;
+                changes = true;
+            }
+        }
+        pointer += 1;
+    }
+    if changes {
+        merge(runs);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AsCharsIter, Hirschberg, HirschbergAlg};
+
+    fn items(runs: &[(char, &str)]) -> Vec<DiffItem<char>> {
+        let mut diff = Vec::new();
+        for &(op, text) in runs {
+            diff.extend(text.chars().map(|c| match op {
+                '-' => DiffItem::First(c),
+                '+' => DiffItem::Second(c),
+                _ => DiffItem::Both(c),
+            }));
+        }
+        diff
+    }
+
+    #[test]
+    fn test_cleanup_semantic() {
+        let diff = items(&[('-', "a"), ('=', "b"), ('-', "c")]);
+        assert_eq!(cleanup_semantic(diff), items(&[('-', "abc"), ('+', "b")]));
+
+        let diff = items(&[('-', "ab"), ('=', "cd"), ('-', "e"), ('=', "f"), ('+', "g")]);
+        assert_eq!(cleanup_semantic(diff), items(&[('-', "abcdef"), ('+', "cdfg")]));
+
+        let diff = items(&[
+            ('+', "1"),
+            ('=', "A"),
+            ('-', "B"),
+            ('+', "2"),
+            ('=', "_"),
+            ('+', "1"),
+            ('=', "A"),
+            ('-', "B"),
+            ('+', "2"),
+        ]);
+        assert_eq!(cleanup_semantic(diff), items(&[('-', "AB_AB"), ('+', "1A2_1A2")]));
+
+        let diff = items(&[('=', "The c"), ('-', "ow and the c"), ('=', "at.")]);
+        assert_eq!(
+            cleanup_semantic(diff),
+            items(&[('=', "The "), ('-', "cow and the "), ('=', "cat.")])
+        );
+
+        let diff = items(&[('-', "abcxx"), ('+', "xxdef")]);
+        assert_eq!(cleanup_semantic(diff.clone()), diff);
+
+        let diff = items(&[('-', "abcxxx"), ('+', "xxxdef")]);
+        assert_eq!(
+            cleanup_semantic(diff),
+            items(&[('-', "abc"), ('=', "xxx"), ('+', "def")])
+        );
+
+        let diff = items(&[('-', "xxxabc"), ('+', "defxxx")]);
+        assert_eq!(
+            cleanup_semantic(diff),
+            items(&[('+', "def"), ('=', "xxx"), ('-', "abc")])
+        );
+
+        let diff = items(&[
+            ('-', "abcd1212"),
+            ('+', "1212efghi"),
+            ('=', "----"),
+            ('-', "A3"),
+            ('+', "3BC"),
+        ]);
+        assert_eq!(
+            cleanup_semantic(diff),
+            items(&[
+                ('-', "abcd"),
+                ('=', "1212"),
+                ('+', "efghi"),
+                ('=', "----"),
+                ('-', "A"),
+                ('=', "3"),
+                ('+', "BC"),
+            ])
+        );
+
+        let diff: Vec<_> = Hirschberg::diff("abcdfghjqvz".chars_iter(), "abcdefgijkrxyz".chars_iter());
+        assert_eq!(
+            cleanup_semantic(diff),
+            items(&[
+                ('=', "abcd"),
+                ('+', "e"),
+                ('=', "fg"),
+                ('-', "hjqv"),
+                ('+', "ijkrxy"),
+                ('=', "z")
+            ])
+        );
+
+        let diff: Vec<_> = Hirschberg::diff("the quick fox".chars_iter(), "the slow dog".chars_iter());
+        assert_eq!(
+            cleanup_semantic(diff),
+            items(&[('=', "the "), ('-', "quick fox"), ('+', "slow dog")])
+        );
+    }
+
+    #[test]
+    fn test_cleanup_semantic_lossless() {
+        let diff = items(&[('=', "AAA\r\n\r\nBBB"), ('+', "\r\nDDD\r\n\r\nBBB"), ('=', "\r\nEEE")]);
+        assert_eq!(
+            cleanup_semantic_lossless(diff),
+            items(&[('=', "AAA\r\n\r\n"), ('+', "BBB\r\nDDD\r\n\r\n"), ('=', "BBB\r\nEEE")])
+        );
+
+        let diff = items(&[('=', "The c"), ('+', "ow and the c"), ('=', "at.")]);
+        assert_eq!(
+            cleanup_semantic_lossless(diff),
+            items(&[('=', "The "), ('+', "cow and the "), ('=', "cat.")])
+        );
+
+        let diff = items(&[('=', "The-c"), ('+', "ow-and-the-c"), ('=', "at.")]);
+        assert_eq!(
+            cleanup_semantic_lossless(diff),
+            items(&[('=', "The-"), ('+', "cow-and-the-"), ('=', "cat.")])
+        );
+
+        let diff = items(&[('=', "a"), ('-', "a"), ('=', "ax")]);
+        assert_eq!(cleanup_semantic_lossless(diff), items(&[('-', "a"), ('=', "aax")]));
+
+        let diff = items(&[('=', "xa"), ('-', "a"), ('=', "a")]);
+        assert_eq!(cleanup_semantic_lossless(diff), items(&[('=', "xaa"), ('-', "a")]));
+    }
+
+    #[test]
+    fn test_cleanup_efficiency() {
+        let diff = items(&[('-', "ab"), ('+', "12"), ('=', "wxyz"), ('-', "cd"), ('+', "34")]);
+        assert_eq!(cleanup_efficiency(diff.clone(), 4), diff);
+
+        let diff = items(&[('-', "ab"), ('+', "12"), ('=', "xyz"), ('-', "cd"), ('+', "34")]);
+        assert_eq!(
+            cleanup_efficiency(diff, 4),
+            items(&[('-', "abxyzcd"), ('+', "12xyz34")])
+        );
+
+        let diff = items(&[('+', "12"), ('=', "x"), ('-', "cd"), ('+', "34")]);
+        assert_eq!(cleanup_efficiency(diff, 4), items(&[('-', "xcd"), ('+', "12x34")]));
+
+        let diff = items(&[
+            ('-', "ab"),
+            ('+', "12"),
+            ('=', "xy"),
+            ('+', "34"),
+            ('=', "z"),
+            ('-', "cd"),
+            ('+', "56"),
+        ]);
+        assert_eq!(
+            cleanup_efficiency(diff, 4),
+            items(&[('-', "abxyzcd"), ('+', "12xy34z56")])
+        );
+
+        let diff = items(&[('-', "ab"), ('+', "12"), ('=', "wxyz"), ('-', "cd"), ('+', "34")]);
+        assert_eq!(
+            cleanup_efficiency(diff, 5),
+            items(&[('-', "abwxyzcd"), ('+', "12wxyz34")])
+        );
+    }
+}
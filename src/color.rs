@@ -0,0 +1,434 @@
+//! The ANSI colored renderings of the unified and side-by-side formats.
+//! The deleted and inserted lines of a replacement are paired, and the words
+//! or chars that differ within a pair of similar lines are emphasized.
+//!
+//! ```
+//! use ng_diff::{color, diff_ops, format, Hirschberg, HirschbergAlg};
+//!
+//! let (first, second) = (format::lines("let x = 1;\n"), format::lines("let y = 1;\n"));
+//! let diff: Vec<_> = Hirschberg::diff(first.iter(), second.iter());
+//! let options = color::ColorOptions::default();
+//! let out = color::unified(&first, &second, &diff_ops(&diff), ("a", "b"), 3, &options);
+//! assert!(out.contains("\x1b[31m-let \x1b[0m\x1b[1;7;31mx\x1b[0m\x1b[31m = 1;\x1b[0m\n"));
+//! ```
+
+use std::{env, error::Error, fmt, io::IsTerminal, str::FromStr};
+
+use crate::{
+    diff_ops,
+    format::{self, push_line, side_by_side_with, unified_range, SideBySideOptions, NO_NEWLINE},
+    hunks, DiffItem, DiffOp, DiffTag, Hirschberg, HirschbergAlg,
+};
+
+/// The lowest share of the equal chars of a pair of lines, the less similar
+/// lines are colored without emphasis
+const MIN_EMPHASIS_RATIO: f64 = 0.4;
+
+/// The SGR parameters of the styles, e.g. `1;31` for the bold red text.
+/// The empty parameters leave the text unstyled.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Palette {
+    /// The file labels of the header
+    pub header: String,
+    /// The ranges of the hunks
+    pub hunk: String,
+    pub deleted: String,
+    pub inserted: String,
+    /// The changed part of a deleted line
+    pub deleted_emphasis: String,
+    /// The changed part of an inserted line
+    pub inserted_emphasis: String,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self {
+            header: "1".to_string(),
+            hunk: "36".to_string(),
+            deleted: "31".to_string(),
+            inserted: "32".to_string(),
+            deleted_emphasis: "1;7;31".to_string(),
+            inserted_emphasis: "1;7;32".to_string(),
+        }
+    }
+}
+
+impl FromStr for Palette {
+    type Err = PaletteError;
+
+    /// Parses the colon separated styles over the default palette,
+    /// e.g. `del=91:ins=92:del-em=41:ins-em=42:header=1:hunk=35`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut palette = Self::default();
+        for entry in s.split(':').filter(|entry| !entry.is_empty()) {
+            let (key, value) = entry
+                .split_once('=')
+                .ok_or_else(|| PaletteError::InvalidEntry(entry.to_string()))?;
+            if !value.chars().all(|c| c.is_ascii_digit() || c == ';') {
+                return Err(PaletteError::InvalidEntry(entry.to_string()));
+            }
+            let style = match key {
+                "header" => &mut palette.header,
+                "hunk" => &mut palette.hunk,
+                "del" => &mut palette.deleted,
+                "ins" => &mut palette.inserted,
+                "del-em" => &mut palette.deleted_emphasis,
+                "ins-em" => &mut palette.inserted_emphasis,
+                _ => return Err(PaletteError::UnknownKey(key.to_string())),
+            };
+            *style = value.to_string();
+        }
+        Ok(palette)
+    }
+}
+
+/// An error of parsing a `Palette`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PaletteError {
+    UnknownKey(String),
+    /// An entry is not a key with the digits and semicolons after `=`
+    InvalidEntry(String),
+}
+
+impl fmt::Display for PaletteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownKey(key) => write!(f, "unknown palette key '{}'", key),
+            Self::InvalidEntry(entry) => write!(f, "invalid palette entry '{}'", entry),
+        }
+    }
+}
+
+impl Error for PaletteError {}
+
+/// The units of the emphasis within the changed lines
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Emphasis {
+    None,
+    Words,
+    Chars,
+}
+
+/// The options of the colored renderings
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ColorOptions {
+    pub palette: Palette,
+    pub emphasis: Emphasis,
+}
+
+impl Default for ColorOptions {
+    fn default() -> Self {
+        Self {
+            palette: Palette::default(),
+            emphasis: Emphasis::Words,
+        }
+    }
+}
+
+/// When to color the output
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorMode {
+    /// Only when the output is a terminal and `NO_COLOR` is not set
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorMode {
+    /// Returns whether to color the output written to the `stream`
+    pub fn is_enabled(self, stream: &impl IsTerminal) -> bool {
+        match self {
+            Self::Auto => stream.is_terminal() && env::var_os("NO_COLOR").map_or(true, |value| value.is_empty()),
+            Self::Always => true,
+            Self::Never => false,
+        }
+    }
+}
+
+/// Formats the diff like `format::unified` with the colored lines
+pub fn unified<T: AsRef<str>>(
+    first: &[T], second: &[T], ops: &[DiffOp], labels: (&str, &str), context_len: usize, options: &ColorOptions,
+) -> String {
+    let hunks = hunks(ops, context_len);
+    if hunks.is_empty() {
+        return String::new();
+    }
+
+    let palette = &options.palette;
+    let mut out = paint(&palette.header, &format!("--- {}", labels.0)) + "\n";
+    out += &(paint(&palette.header, &format!("+++ {}", labels.1)) + "\n");
+    for hunk in hunks {
+        let ranges = format!("@@ -{} +{} @@", unified_range(&hunk.first), unified_range(&hunk.second));
+        out += &(paint(&palette.hunk, &ranges) + "\n");
+
+        for op in &hunk.ops {
+            let (deleted, inserted) = (&first[op.first.clone()], &second[op.second.clone()]);
+            if op.tag == DiffTag::Equal {
+                for line in deleted {
+                    push_line(&mut out, " ", line.as_ref());
+                }
+                continue;
+            }
+
+            let refined: Vec<_> = deleted
+                .iter()
+                .zip(inserted)
+                .map(|(deleted, inserted)| {
+                    refine(
+                        strip_newline(deleted.as_ref()),
+                        strip_newline(inserted.as_ref()),
+                        options.emphasis,
+                    )
+                })
+                .collect();
+            for (index, line) in deleted.iter().enumerate() {
+                let spans = refined
+                    .get(index)
+                    .and_then(|spans| spans.as_ref().map(|(spans, _)| &spans[..]));
+                let styles = (palette.deleted.as_str(), palette.deleted_emphasis.as_str());
+                push_colored_line(&mut out, "-", line.as_ref(), spans, styles);
+            }
+            for (index, line) in inserted.iter().enumerate() {
+                let spans = refined
+                    .get(index)
+                    .and_then(|spans| spans.as_ref().map(|(_, spans)| &spans[..]));
+                let styles = (palette.inserted.as_str(), palette.inserted_emphasis.as_str());
+                push_colored_line(&mut out, "+", line.as_ref(), spans, styles);
+            }
+        }
+    }
+    out
+}
+
+/// Formats the diff like `format::side_by_side` with the colored sides
+/// and markers of the changed rows
+pub fn side_by_side<T: AsRef<str>>(
+    first: &[T], second: &[T], ops: &[DiffOp], side_by_side_options: &SideBySideOptions, options: &ColorOptions,
+) -> String {
+    let palette = &options.palette;
+    fn styled<'a, 'p>(
+        spans: Vec<(bool, &'a str)>, style: &'p str, emphasis: &'p str,
+    ) -> Vec<(Option<&'p str>, &'a str)> {
+        spans
+            .into_iter()
+            .map(|(is_changed, text)| (Some(if is_changed { emphasis } else { style }), text))
+            .collect()
+    }
+    let (deleted, inserted) = (palette.deleted.as_str(), palette.inserted.as_str());
+
+    side_by_side_with(
+        first,
+        second,
+        ops,
+        side_by_side_options,
+        |left, marker, right| {
+            let (left, right) = (left.unwrap_or_default(), right.unwrap_or_default());
+            match marker {
+                '<' => (vec![(Some(deleted), left)], Some(deleted), Vec::new()),
+                '>' => (Vec::new(), Some(inserted), vec![(Some(inserted), right)]),
+                '|' => {
+                    let (left_spans, right_spans) = refine(left, right, options.emphasis)
+                        .unwrap_or_else(|| (vec![(false, left)], vec![(false, right)]));
+                    (
+                        styled(left_spans, deleted, &palette.deleted_emphasis),
+                        None,
+                        styled(right_spans, inserted, &palette.inserted_emphasis),
+                    )
+                }
+                _ => (vec![(None, left)], None, vec![(None, right)]),
+            }
+        },
+        |style, text| paint(style.unwrap_or_default(), text),
+    )
+}
+
+/// Splits the pair of lines into the equal and the changed spans of the words
+/// or chars, unless the lines are too different
+#[allow(clippy::type_complexity)]
+pub(crate) fn refine<'a>(
+    deleted: &'a str, inserted: &'a str, emphasis: Emphasis,
+) -> Option<(Vec<(bool, &'a str)>, Vec<(bool, &'a str)>)> {
+    let split = match emphasis {
+        Emphasis::None => return None,
+        Emphasis::Words => format::words,
+        Emphasis::Chars => format::chars,
+    };
+    let (first, second) = (split(deleted), split(inserted));
+    let diff: Vec<DiffItem<_>> = Hirschberg::diff(first.iter(), second.iter());
+    let ops = diff_ops(&diff);
+
+    let equal_len: usize = ops
+        .iter()
+        .filter(|op| op.tag == DiffTag::Equal)
+        .flat_map(|op| &first[op.first.clone()])
+        .map(|item| item.chars().count())
+        .sum();
+    let total_len = deleted.chars().count() + inserted.chars().count();
+    if total_len == 0 || (2 * equal_len) as f64 / (total_len as f64) < MIN_EMPHASIS_RATIO {
+        return None;
+    }
+
+    let spans = |items: &[&'a str], text: &'a str, range: fn(&DiffOp) -> std::ops::Range<usize>| {
+        let mut offset = 0;
+        let mut offsets = vec![0];
+        for item in items {
+            offset += item.len();
+            offsets.push(offset);
+        }
+        ops.iter()
+            .map(|op| {
+                let range = range(op);
+                (
+                    op.tag != DiffTag::Equal,
+                    &text[offsets[range.start]..offsets[range.end]],
+                )
+            })
+            .filter(|(_, text)| !text.is_empty())
+            .collect()
+    };
+    Some((
+        spans(&first, deleted, |op| op.first.clone()),
+        spans(&second, inserted, |op| op.second.clone()),
+    ))
+}
+
+/// Pushes the line with the changed spans in the emphasis style
+/// of the `(style, emphasis)` pair
+fn push_colored_line(out: &mut String, prefix: &str, line: &str, spans: Option<&[(bool, &str)]>, styles: (&str, &str)) {
+    let (style, emphasis) = styles;
+    let text = strip_newline(line);
+    let mut segments = vec![(style, prefix)];
+    match spans {
+        Some(spans) => {
+            segments.extend(
+                spans
+                    .iter()
+                    .map(|(is_changed, span)| (if *is_changed { emphasis } else { style }, *span)),
+            );
+        }
+        None => segments.push((style, text)),
+    }
+
+    let mut painted: Vec<(&str, String)> = Vec::new();
+    for (style, text) in segments {
+        match painted.last_mut() {
+            Some((last, joined)) if *last == style => joined.push_str(text),
+            _ => painted.push((style, text.to_string())),
+        }
+    }
+    for (style, text) in painted {
+        out.push_str(&paint(style, &text));
+    }
+    out.push('\n');
+    if !line.ends_with('\n') {
+        out.push_str(NO_NEWLINE);
+    }
+}
+
+/// Strips only the `\n` terminator, since a `\r` before it is a part of the line
+fn strip_newline(line: &str) -> &str {
+    line.strip_suffix('\n').unwrap_or(line)
+}
+
+/// Wraps the text into the SGR escape sequences of the style
+fn paint(style: &str, text: &str) -> String {
+    if style.is_empty() || text.is_empty() {
+        text.to_string()
+    } else {
+        format!("\x1b[{}m{}\x1b[0m", style, text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line_ops(first: &[&str], second: &[&str]) -> Vec<DiffOp> {
+        let diff: Vec<_> = Hirschberg::diff(first.iter(), second.iter());
+        diff_ops(&diff)
+    }
+
+    #[test]
+    fn test_palette() {
+        let palette: Palette = "del=91:ins-em=4;32".parse().unwrap();
+        assert_eq!(palette.deleted, "91");
+        assert_eq!(palette.inserted_emphasis, "4;32");
+        assert_eq!(palette.inserted, "32");
+        assert_eq!("".parse(), Ok(Palette::default()));
+        assert_eq!(
+            "color=31".parse::<Palette>(),
+            Err(PaletteError::UnknownKey("color".to_string()))
+        );
+        assert_eq!(
+            "del=red".parse::<Palette>(),
+            Err(PaletteError::InvalidEntry("del=red".to_string()))
+        );
+        assert_eq!(
+            "del".parse::<Palette>(),
+            Err(PaletteError::InvalidEntry("del".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_refine() {
+        assert_eq!(
+            refine("the quick fox\r", "the slow fox\r", Emphasis::Words),
+            Some((
+                vec![(false, "the "), (true, "quick"), (false, " fox\r")],
+                vec![(false, "the "), (true, "slow"), (false, " fox\r")]
+            ))
+        );
+        assert_eq!(
+            refine("color", "colour", Emphasis::Chars),
+            Some((
+                vec![(false, "colo"), (false, "r")],
+                vec![(false, "colo"), (true, "u"), (false, "r")]
+            ))
+        );
+        assert_eq!(refine("abc", "xyz", Emphasis::Chars), None);
+        assert_eq!(refine("abc", "abd", Emphasis::None), None);
+    }
+
+    #[test]
+    fn test_unified() {
+        let (first, second) = (
+            format::lines("a\nthe quick fox\nc"),
+            format::lines("a\nthe slow fox\nxyz\nc"),
+        );
+        let ops = line_ops(&first, &second);
+        let options = ColorOptions::default();
+        assert_eq!(
+            unified(&first, &second, &ops, ("x", "y"), 1, &options),
+            "\x1b[1m--- x\x1b[0m\n\x1b[1m+++ y\x1b[0m\n\x1b[36m@@ -1,3 +1,4 @@\x1b[0m\n a\n\
+             \x1b[31m-the \x1b[0m\x1b[1;7;31mquick\x1b[0m\x1b[31m fox\x1b[0m\n\
+             \x1b[32m+the \x1b[0m\x1b[1;7;32mslow\x1b[0m\x1b[32m fox\x1b[0m\n\x1b[32m+xyz\x1b[0m\n c\n\\ No newline at end of file\n"
+        );
+
+        let options = ColorOptions {
+            emphasis: Emphasis::None,
+            ..ColorOptions::default()
+        };
+        assert_eq!(
+            unified(&first, &second, &ops, ("x", "y"), 0, &options),
+            "\x1b[1m--- x\x1b[0m\n\x1b[1m+++ y\x1b[0m\n\x1b[36m@@ -2 +2,2 @@\x1b[0m\n\
+             \x1b[31m-the quick fox\x1b[0m\n\x1b[32m+the slow fox\x1b[0m\n\x1b[32m+xyz\x1b[0m\n"
+        );
+    }
+
+    #[test]
+    fn test_side_by_side() {
+        let (first, second) = (format::lines("a\nab cd\nx\n"), format::lines("a\nab ce\n"));
+        let ops = line_ops(&first, &second);
+        assert_eq!(
+            side_by_side(
+                &first,
+                &second,
+                &ops,
+                &SideBySideOptions::new(19),
+                &ColorOptions::default()
+            ),
+            "a          a\n\x1b[31mab \x1b[0m\x1b[1;7;31mcd\x1b[0m    | \x1b[32mab \x1b[0m\x1b[1;7;32mce\x1b[0m\n\
+             \x1b[31mx\x1b[0m        \x1b[31m<\x1b[0m\n"
+        );
+    }
+}
@@ -0,0 +1,580 @@
+//! The byte-level delta that rebuilds a target from a source by copying
+//! the ranges of the source and inserting the new bytes. Its serialized form
+//! is the delta format of Git packs, so the deltas of large binary artifacts
+//! stay compact. The encoder looks up the blocks of the target in the hash
+//! index of the source instead of computing the LCS, which is too slow
+//! for such inputs.
+
+use std::{collections::HashMap, error::Error, fmt};
+
+/// The length of the source blocks in the index of the encoder,
+/// the shorter matches are inserted
+const BLOCK_LEN: usize = 16;
+/// The maximum number of the source offsets of the same block that are tried
+const MAX_CANDIDATES: usize = 64;
+/// The maximum length of a copy instruction of the format
+const MAX_COPY_LEN: usize = 0xff_ffff;
+/// The maximum length of an insert instruction of the format
+const MAX_INSERT_LEN: usize = 0x7f;
+/// The maximum end of a copied source range, so the offsets of all copy
+/// instructions of the format fit in 32 bits
+const MAX_COPY_END: usize = u32::MAX as usize;
+
+/// An instruction of a `Delta`. With the `serde` feature it is serialized as
+/// `{"op":"copy","value":{"offset":x,"len":y}}` or `{"op":"insert","value":[bytes]}`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "op", content = "value", rename_all = "lowercase"))]
+pub enum DeltaOp {
+    /// Copies `len` bytes of the source from the `offset`
+    Copy { offset: usize, len: usize },
+    /// Inserts the bytes
+    Insert(Vec<u8>),
+}
+
+/// The instructions that turn a source of the known length into a target.
+/// With the `serde` feature it is deserialized with the same checks
+/// as by `from_bytes`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "RawDelta"))]
+pub struct Delta {
+    source_len: usize,
+    target_len: usize,
+    ops: Vec<DeltaOp>,
+}
+
+impl Delta {
+    pub fn new(source_len: usize) -> Self {
+        Self {
+            source_len,
+            target_len: 0,
+            ops: Vec::new(),
+        }
+    }
+
+    /// Encodes the target as the copies of the source blocks that are found
+    /// in it and the insertions of the remaining bytes
+    pub fn encode(source: &[u8], target: &[u8]) -> Self {
+        let mut index: HashMap<&[u8], Vec<usize>> = HashMap::new();
+        let indexed_len = source.len().min(MAX_COPY_END);
+        for offset in (0..indexed_len.saturating_sub(BLOCK_LEN - 1)).step_by(BLOCK_LEN) {
+            let offsets = index.entry(&source[offset..offset + BLOCK_LEN]).or_default();
+            if offsets.len() < MAX_CANDIDATES {
+                offsets.push(offset);
+            }
+        }
+
+        let mut delta = Self::new(source.len());
+        let mut i = 0;
+        while i < target.len() {
+            let best = target.get(i..i + BLOCK_LEN).and_then(|block| {
+                index
+                    .get(block)?
+                    .iter()
+                    .fold(None, |best: Option<(usize, usize)>, &offset| {
+                        let len = common_prefix_len(&source[offset..MAX_COPY_END.min(source.len())], &target[i..]);
+                        match best {
+                            Some((_, best_len)) if best_len >= len => best,
+                            _ => Some((offset, len)),
+                        }
+                    })
+            });
+
+            match best {
+                Some((mut offset, len)) => {
+                    // Takes back the inserted bytes that precede the match in the source too
+                    let mut back = 0;
+                    if let Some(DeltaOp::Insert(bytes)) = delta.ops.last_mut() {
+                        while back < bytes.len()
+                            && back < offset
+                            && bytes[bytes.len() - back - 1] == source[offset - back - 1]
+                        {
+                            back += 1;
+                        }
+                        bytes.truncate(bytes.len() - back);
+                        if bytes.is_empty() {
+                            delta.ops.pop();
+                        }
+                        delta.target_len -= back;
+                        offset -= back;
+                    }
+                    delta.push_copy(offset, len + back);
+                    i += len;
+                }
+                None => {
+                    delta.push_insert(&target[i..i + 1]);
+                    i += 1;
+                }
+            }
+        }
+        delta
+    }
+
+    pub fn source_len(&self) -> usize {
+        self.source_len
+    }
+
+    pub fn target_len(&self) -> usize {
+        self.target_len
+    }
+
+    pub fn ops(&self) -> &[DeltaOp] {
+        &self.ops
+    }
+
+    /// Appends the copy of the source range, joining it with the preceding
+    /// adjacent copy.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of the source or ends beyond `u32::MAX`,
+    /// as the offsets of the format are 32-bit.
+    pub fn push_copy(&mut self, offset: usize, len: usize) {
+        assert!(
+            offset.checked_add(len).is_some_and(|end| end <= MAX_COPY_END),
+            "the copy {}..{} does not fit in 32 bits",
+            offset,
+            offset.saturating_add(len)
+        );
+        assert!(
+            offset.checked_add(len).is_some_and(|end| end <= self.source_len),
+            "the copy {}..{} is out of the source",
+            offset,
+            offset.saturating_add(len)
+        );
+        if len == 0 {
+            return;
+        }
+        self.target_len += len;
+        match self.ops.last_mut() {
+            Some(DeltaOp::Copy {
+                offset: last_offset,
+                len: last_len,
+            }) if *last_offset + *last_len == offset => *last_len += len,
+            _ => self.ops.push(DeltaOp::Copy { offset, len }),
+        }
+    }
+
+    /// Appends the insertion of the bytes, joining it with the preceding insertion
+    pub fn push_insert(&mut self, bytes: &[u8]) {
+        if bytes.is_empty() {
+            return;
+        }
+        self.target_len += bytes.len();
+        match self.ops.last_mut() {
+            Some(DeltaOp::Insert(last)) => last.extend_from_slice(bytes),
+            _ => self.ops.push(DeltaOp::Insert(bytes.to_vec())),
+        }
+    }
+
+    /// Rebuilds the target from the source
+    pub fn apply(&self, source: &[u8]) -> Result<Vec<u8>, DeltaError> {
+        if source.len() != self.source_len {
+            return Err(DeltaError::SourceLen {
+                expected: self.source_len,
+                actual: source.len(),
+            });
+        }
+        let mut target = Vec::with_capacity(self.target_len);
+        for op in &self.ops {
+            match op {
+                DeltaOp::Copy { offset, len } => target.extend_from_slice(&source[*offset..*offset + *len]),
+                DeltaOp::Insert(bytes) => target.extend_from_slice(bytes),
+            }
+        }
+        Ok(target)
+    }
+
+    /// Serializes the delta: the varint lengths of the source and the target
+    /// followed by the copy and insert instructions
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        write_varint(&mut bytes, self.source_len);
+        write_varint(&mut bytes, self.target_len);
+
+        for op in &self.ops {
+            match op {
+                DeltaOp::Copy { offset, len } => {
+                    let (mut offset, end) = (*offset, *offset + *len);
+                    while offset < end {
+                        let len = (end - offset).min(MAX_COPY_LEN);
+                        let start = bytes.len();
+                        bytes.push(0x80);
+                        // The zero bytes of the offset and the length are omitted,
+                        // the omitted length of 0x10000 is not used
+                        for (bit, byte) in (offset as u32).to_le_bytes().iter().enumerate() {
+                            if *byte != 0 {
+                                bytes[start] |= 1 << bit;
+                                bytes.push(*byte);
+                            }
+                        }
+                        for (bit, byte) in (len as u32).to_le_bytes()[..3].iter().enumerate() {
+                            if *byte != 0 {
+                                bytes[start] |= 0x10 << bit;
+                                bytes.push(*byte);
+                            }
+                        }
+                        offset += len;
+                    }
+                }
+                DeltaOp::Insert(inserted) => {
+                    for chunk in inserted.chunks(MAX_INSERT_LEN) {
+                        bytes.push(chunk.len() as u8);
+                        bytes.extend_from_slice(chunk);
+                    }
+                }
+            }
+        }
+        bytes
+    }
+
+    /// Parses the serialized delta
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DeltaError> {
+        let mut position = 0;
+        let source_len = read_varint(bytes, &mut position)?;
+        let target_len = read_varint(bytes, &mut position)?;
+
+        let mut delta = Self::new(source_len);
+        while position < bytes.len() {
+            let instruction = bytes[position];
+            position += 1;
+            if instruction & 0x80 != 0 {
+                let mut read_le = |bits: u8, count: usize| {
+                    let mut value = 0;
+                    for index in 0..count {
+                        if bits & (1 << index) != 0 {
+                            let byte = *bytes.get(position).ok_or(DeltaError::Truncated)?;
+                            value |= (byte as usize) << (8 * index);
+                            position += 1;
+                        }
+                    }
+                    Ok(value)
+                };
+                let offset = read_le(instruction & 0x0f, 4)?;
+                let len = match read_le((instruction >> 4) & 0x07, 3)? {
+                    0 => 0x10000,
+                    len => len,
+                };
+                if offset
+                    .checked_add(len)
+                    .map_or(true, |end| end > source_len || end > MAX_COPY_END)
+                {
+                    return Err(DeltaError::CopyOutOfBounds { offset, len });
+                }
+                delta.push_copy(offset, len);
+            } else if instruction != 0 {
+                let end = position + instruction as usize;
+                delta.push_insert(bytes.get(position..end).ok_or(DeltaError::Truncated)?);
+                position = end;
+            } else {
+                return Err(DeltaError::ReservedInstruction { position: position - 1 });
+            }
+        }
+
+        if delta.target_len != target_len {
+            return Err(DeltaError::TargetLen {
+                expected: target_len,
+                actual: delta.target_len,
+            });
+        }
+        Ok(delta)
+    }
+}
+
+/// The fields of a deserialized `Delta` that are not checked yet
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct RawDelta {
+    source_len: usize,
+    target_len: usize,
+    ops: Vec<DeltaOp>,
+}
+
+#[cfg(feature = "serde")]
+impl std::convert::TryFrom<RawDelta> for Delta {
+    type Error = DeltaError;
+
+    fn try_from(raw: RawDelta) -> Result<Self, DeltaError> {
+        let mut delta = Self::new(raw.source_len);
+        let mut target_len = 0usize;
+        for op in raw.ops {
+            match op {
+                DeltaOp::Copy { offset, len } => {
+                    // The offsets beyond 32 bits do not fit in the format
+                    if offset
+                        .checked_add(len)
+                        .map_or(true, |end| end > delta.source_len || end > MAX_COPY_END)
+                    {
+                        return Err(DeltaError::CopyOutOfBounds { offset, len });
+                    }
+                    target_len = target_len.checked_add(len).ok_or(DeltaError::Overflow)?;
+                    delta.push_copy(offset, len);
+                }
+                DeltaOp::Insert(bytes) => {
+                    target_len = target_len.checked_add(bytes.len()).ok_or(DeltaError::Overflow)?;
+                    delta.push_insert(&bytes);
+                }
+            }
+        }
+
+        if target_len != raw.target_len {
+            return Err(DeltaError::TargetLen {
+                expected: raw.target_len,
+                actual: target_len,
+            });
+        }
+        Ok(delta)
+    }
+}
+
+fn common_prefix_len(a: &[u8], b: &[u8]) -> usize {
+    a.iter().zip(b).take_while(|(x, y)| x == y).count()
+}
+
+/// Writes the value by 7 bits from the lowest ones, the high bit marks
+/// that more bytes follow
+fn write_varint(bytes: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+fn read_varint(bytes: &[u8], position: &mut usize) -> Result<usize, DeltaError> {
+    let mut value = 0usize;
+    let mut shift = 0;
+    loop {
+        let byte = *bytes.get(*position).ok_or(DeltaError::Truncated)?;
+        *position += 1;
+        let bits = ((byte & 0x7f) as usize)
+            .checked_shl(shift)
+            .filter(|bits| bits >> shift == (byte & 0x7f) as usize)
+            .ok_or(DeltaError::Overflow)?;
+        value |= bits;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+        shift += 7;
+    }
+}
+
+/// An error of parsing or applying a `Delta`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DeltaError {
+    /// The delta ends in the middle of an instruction or a length
+    Truncated,
+    /// A length of the header does not fit in `usize`
+    Overflow,
+    /// The zero instruction byte at the position, which is reserved
+    ReservedInstruction { position: usize },
+    /// A copy out of the source
+    CopyOutOfBounds { offset: usize, len: usize },
+    /// The instructions build a target of another length than the header says
+    TargetLen { expected: usize, actual: usize },
+    /// The delta is applied to a source of another length
+    SourceLen { expected: usize, actual: usize },
+}
+
+impl fmt::Display for DeltaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Truncated => write!(f, "the delta is truncated"),
+            Self::Overflow => write!(f, "the delta length overflows"),
+            Self::ReservedInstruction { position } => write!(f, "reserved delta instruction at {}", position),
+            Self::CopyOutOfBounds { offset, len } => {
+                write!(f, "the copy of {} bytes at {} is out of the source", len, offset)
+            }
+            Self::TargetLen { expected, actual } => {
+                write!(f, "the delta builds {} bytes instead of {}", actual, expected)
+            }
+            Self::SourceLen { expected, actual } => {
+                write!(f, "the source has {} bytes instead of {}", actual, expected)
+            }
+        }
+    }
+}
+
+impl Error for DeltaError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode() {
+        let mut state = 1u32;
+        let source: Vec<u8> = (0..4096)
+            .map(|_| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                (state >> 16) as u8
+            })
+            .collect();
+        let mut target = source[1024..3072].to_vec();
+        target.extend_from_slice(b"a new header");
+        target.extend_from_slice(&source[..1000]);
+        target.push(0);
+        target.extend_from_slice(&source[3000..]);
+
+        let delta = Delta::encode(&source, &target);
+        assert_eq!(delta.apply(&source), Ok(target.clone()));
+        assert_eq!(delta.target_len(), target.len());
+        assert_eq!(
+            delta.ops(),
+            &[
+                DeltaOp::Copy {
+                    offset: 1024,
+                    len: 2048
+                },
+                DeltaOp::Insert(b"a new header".to_vec()),
+                DeltaOp::Copy { offset: 0, len: 1000 },
+                DeltaOp::Insert(vec![0]),
+                DeltaOp::Copy {
+                    offset: 3000,
+                    len: 1096
+                },
+            ]
+        );
+
+        let bytes = delta.to_bytes();
+        assert!(bytes.len() < 40);
+        assert_eq!(Delta::from_bytes(&bytes), Ok(delta));
+
+        let delta = Delta::encode(b"", b"new");
+        assert_eq!(delta.ops(), &[DeltaOp::Insert(b"new".to_vec())]);
+        assert_eq!(Delta::encode(b"old", b"").ops(), &[]);
+    }
+
+    #[test]
+    fn test_to_bytes() {
+        let mut delta = Delta::new(0x20000);
+        delta.push_copy(0x10, 0x100);
+        delta.push_insert(&[b'x'; 130]);
+        delta.push_copy(0x10000, 0x10000);
+
+        let mut expected = vec![0x80, 0x80, 0x08, 0x82, 0x83, 0x04];
+        expected.extend_from_slice(&[0x80 | 0x01 | 0x20, 0x10, 0x01]);
+        expected.push(127);
+        expected.extend_from_slice(&[b'x'; 127]);
+        expected.push(3);
+        expected.extend_from_slice(b"xxx");
+        expected.extend_from_slice(&[0x80 | 0x04 | 0x40, 0x01, 0x01]);
+        assert_eq!(delta.to_bytes(), expected);
+
+        // The copy of 0x10000 bytes may omit its length
+        assert_eq!(Delta::from_bytes(&[0x80, 0x80, 0x04, 0x80, 0x80, 0x04, 0x80]), {
+            let mut delta = Delta::new(0x10000);
+            delta.push_copy(0, 0x10000);
+            Ok(delta)
+        });
+    }
+
+    #[test]
+    fn test_from_bytes_errors() {
+        assert_eq!(Delta::from_bytes(&[]), Err(DeltaError::Truncated));
+        assert_eq!(Delta::from_bytes(&[0x80]), Err(DeltaError::Truncated));
+        assert_eq!(Delta::from_bytes(&[0xff; 12]), Err(DeltaError::Overflow));
+        assert_eq!(
+            Delta::from_bytes(&[4, 4, 0]),
+            Err(DeltaError::ReservedInstruction { position: 2 })
+        );
+        assert_eq!(
+            Delta::from_bytes(&[4, 4, 0x91, 2, 4]),
+            Err(DeltaError::CopyOutOfBounds { offset: 2, len: 4 })
+        );
+        assert_eq!(Delta::from_bytes(&[4, 4, 3, b'a']), Err(DeltaError::Truncated));
+        assert_eq!(
+            Delta::from_bytes(&[4, 4, 3, b'a', b'b', b'c']),
+            Err(DeltaError::TargetLen { expected: 4, actual: 3 })
+        );
+
+        let delta = Delta::from_bytes(&[4, 2, 0x91, 2, 2]).unwrap();
+        assert_eq!(delta.apply(b"abcd"), Ok(b"cd".to_vec()));
+        assert_eq!(
+            delta.apply(b"abc"),
+            Err(DeltaError::SourceLen { expected: 4, actual: 3 })
+        );
+    }
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn test_32_bit_offsets() {
+        let mut delta = Delta::new(1 << 33);
+        delta.push_copy(MAX_COPY_END - 0x100_0000, 0x80_0000);
+        delta.push_copy(MAX_COPY_END - 0x80_0000, 0x80_0000);
+        assert_eq!(
+            delta.ops(),
+            &[DeltaOp::Copy {
+                offset: MAX_COPY_END - 0x100_0000,
+                len: 0x100_0000
+            }]
+        );
+        assert_eq!(Delta::from_bytes(&delta.to_bytes()), Ok(delta));
+
+        // The copy of a byte at the offset u32::MAX ends beyond 32 bits
+        let bytes = [
+            0x80,
+            0x80,
+            0x80,
+            0x80,
+            0x20,
+            0x01,
+            0x80 | 0x0f | 0x10,
+            0xff,
+            0xff,
+            0xff,
+            0xff,
+            0x01,
+        ];
+        assert_eq!(
+            Delta::from_bytes(&bytes),
+            Err(DeltaError::CopyOutOfBounds {
+                offset: MAX_COPY_END,
+                len: 1
+            })
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "does not fit in 32 bits")]
+    fn test_push_copy_beyond_32_bits() {
+        let mut delta = Delta::new(usize::MAX);
+        delta.push_copy(MAX_COPY_END, 1);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let mut delta = Delta::new(4);
+        delta.push_copy(2, 2);
+        delta.push_insert(b"ab");
+        let json = serde_json::to_string(&delta).unwrap();
+        assert_eq!(
+            json,
+            r#"{"source_len":4,"target_len":4,"ops":[{"op":"copy","value":{"offset":2,"len":2}},{"op":"insert","value":[97,98]}]}"#
+        );
+        assert_eq!(serde_json::from_str::<Delta>(&json).unwrap(), delta);
+
+        let error = serde_json::from_str::<Delta>(
+            r#"{"source_len":4,"target_len":4,"ops":[{"op":"copy","value":{"offset":2,"len":4}}]}"#,
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            DeltaError::CopyOutOfBounds { offset: 2, len: 4 }.to_string()
+        );
+        let error =
+            serde_json::from_str::<Delta>(r#"{"source_len":4,"target_len":3,"ops":[{"op":"insert","value":[97,98]}]}"#)
+                .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            DeltaError::TargetLen { expected: 3, actual: 2 }.to_string()
+        );
+        let json = format!(
+            r#"{{"source_len":{0},"target_len":0,"ops":[{{"op":"copy","value":{{"offset":0,"len":{0}}}}},{{"op":"copy","value":{{"offset":0,"len":{0}}}}}]}}"#,
+            usize::MAX
+        );
+        assert!(serde_json::from_str::<Delta>(&json).is_err());
+    }
+}
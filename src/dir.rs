@@ -0,0 +1,367 @@
+//! The comparison of two directory trees like `diff -r`. It pairs the entries
+//! of the trees by their relative paths, following the symbolic links, and
+//! reads the paired files for `diff_contents`.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// The options of `compare_dirs`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DirOptions {
+    /// Whether to compare the common subdirectories too
+    pub recursive: bool,
+    /// Whether to compare the files missing from one of the trees
+    /// as the empty files, like the `-N` option of diff
+    pub new_file: bool,
+    /// The patterns of the names of the files and directories to skip,
+    /// with the `*`, `?` and `[...]` wildcards
+    pub exclude: Vec<String>,
+}
+
+/// An entry of the comparison, its path is relative to the roots of the trees
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DirEntry {
+    /// The files to compare
+    Files(PathBuf),
+    /// The file that is missing from the second tree and compared as an empty one
+    DeletedFile(PathBuf),
+    /// The file that is missing from the first tree and compared as an empty one
+    AddedFile(PathBuf),
+    /// The file or directory that exists only in the first tree
+    OnlyInFirst(PathBuf),
+    /// The file or directory that exists only in the second tree
+    OnlyInSecond(PathBuf),
+    /// The subdirectories that are not compared without the `recursive` option
+    CommonDirs(PathBuf),
+    /// The path that is a directory in one tree and a file in the other
+    Mismatch(PathBuf),
+    /// The symbolic link of the first tree whose target does not exist
+    DanglingInFirst(PathBuf),
+    /// The symbolic link of the second tree whose target does not exist
+    DanglingInSecond(PathBuf),
+    /// The subdirectory of the first tree that links to a directory containing it
+    LoopInFirst(PathBuf),
+    /// The subdirectory of the second tree that links to a directory containing it
+    LoopInSecond(PathBuf),
+}
+
+impl DirEntry {
+    pub fn path(&self) -> &Path {
+        match self {
+            Self::Files(path)
+            | Self::DeletedFile(path)
+            | Self::AddedFile(path)
+            | Self::OnlyInFirst(path)
+            | Self::OnlyInSecond(path)
+            | Self::CommonDirs(path)
+            | Self::Mismatch(path)
+            | Self::DanglingInFirst(path)
+            | Self::DanglingInSecond(path)
+            | Self::LoopInFirst(path)
+            | Self::LoopInSecond(path) => path,
+        }
+    }
+
+    /// Reads the files of the entry in the trees of the `first` and `second` roots,
+    /// a file missing from one tree is read as an empty one. Returns `None`
+    /// unless the entry is a pair of files.
+    pub fn read(&self, first: &Path, second: &Path) -> io::Result<Option<(Vec<u8>, Vec<u8>)>> {
+        let read = |root: &Path, exists: bool| {
+            let path = root.join(self.path());
+            if exists {
+                fs::read(&path).map_err(|error| io::Error::new(error.kind(), format!("{}: {}", path.display(), error)))
+            } else {
+                Ok(Vec::new())
+            }
+        };
+        match self {
+            Self::Files(_) => Ok(Some((read(first, true)?, read(second, true)?))),
+            Self::DeletedFile(_) => Ok(Some((read(first, true)?, read(second, false)?))),
+            Self::AddedFile(_) => Ok(Some((read(first, false)?, read(second, true)?))),
+            _ => Ok(None),
+        }
+    }
+}
+
+/// The kind of a path, where the symbolic links are followed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Kind {
+    File,
+    Dir,
+    /// The symbolic link whose target does not exist
+    Dangling,
+}
+
+impl Kind {
+    fn of(path: &Path) -> io::Result<Option<Self>> {
+        let metadata = match fs::symlink_metadata(path) {
+            Ok(metadata) if metadata.file_type().is_symlink() => match fs::metadata(path) {
+                Ok(metadata) => metadata,
+                Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Some(Self::Dangling)),
+                Err(error) => return Err(error),
+            },
+            Ok(metadata) => metadata,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(error),
+        };
+        Ok(Some(if metadata.is_dir() { Self::Dir } else { Self::File }))
+    }
+}
+
+/// Walks both directory trees in the order of the names of the entries
+/// and returns their pairs, with the contents of a directory right after it.
+/// The links to the directories that contain them are reported instead of
+/// walked, so the symbolic link loops end.
+pub fn compare_dirs(first: &Path, second: &Path, options: &DirOptions) -> io::Result<Vec<DirEntry>> {
+    let mut walk = Walk {
+        options,
+        entries: Vec::new(),
+        ancestors: (Vec::new(), Vec::new()),
+    };
+    walk.walk(Some(first), Some(second), Path::new(""))?;
+    Ok(walk.entries)
+}
+
+struct Walk<'a> {
+    options: &'a DirOptions,
+    entries: Vec<DirEntry>,
+    /// The canonical paths of the directories being walked in the first
+    /// and the second tree
+    ancestors: (Vec<PathBuf>, Vec<PathBuf>),
+}
+
+impl Walk<'_> {
+    fn walk(&mut self, first: Option<&Path>, second: Option<&Path>, relative: &Path) -> io::Result<()> {
+        let first_canonical = first.map(fs::canonicalize).transpose()?;
+        let second_canonical = second.map(fs::canonicalize).transpose()?;
+        let is_loop = |canonical: &Option<PathBuf>, ancestors: &[PathBuf]| {
+            canonical.as_ref().is_some_and(|path| ancestors.contains(path))
+        };
+        let first_loops = is_loop(&first_canonical, &self.ancestors.0);
+        let second_loops = is_loop(&second_canonical, &self.ancestors.1);
+        if first_loops {
+            self.entries.push(DirEntry::LoopInFirst(relative.to_path_buf()));
+        }
+        if second_loops {
+            self.entries.push(DirEntry::LoopInSecond(relative.to_path_buf()));
+        }
+        if first_loops || second_loops {
+            return Ok(());
+        }
+
+        self.ancestors.0.extend(first_canonical);
+        self.ancestors.1.extend(second_canonical);
+        let result = self.walk_names(first, second, relative);
+        if first.is_some() {
+            self.ancestors.0.pop();
+        }
+        if second.is_some() {
+            self.ancestors.1.pop();
+        }
+        result
+    }
+
+    fn walk_names(&mut self, first: Option<&Path>, second: Option<&Path>, relative: &Path) -> io::Result<()> {
+        let options = self.options;
+        let mut names = Vec::new();
+        for dir in first.iter().chain(second.iter()) {
+            for entry in fs::read_dir(dir)? {
+                let name = entry?.file_name();
+                let is_excluded = options
+                    .exclude
+                    .iter()
+                    .any(|pattern| matches_pattern(pattern, &name.to_string_lossy()));
+                if !is_excluded {
+                    names.push(name);
+                }
+            }
+        }
+        names.sort();
+        names.dedup();
+
+        for name in names {
+            let path = relative.join(&name);
+            let first_path = first.map(|dir| dir.join(&name));
+            let second_path = second.map(|dir| dir.join(&name));
+            let first_kind = first_path.as_deref().map(Kind::of).transpose()?.flatten();
+            let second_kind = second_path.as_deref().map(Kind::of).transpose()?.flatten();
+
+            if first_kind == Some(Kind::Dangling) {
+                self.entries.push(DirEntry::DanglingInFirst(path.clone()));
+            }
+            if second_kind == Some(Kind::Dangling) {
+                self.entries.push(DirEntry::DanglingInSecond(path.clone()));
+            }
+
+            match (first_kind, second_kind) {
+                (Some(Kind::Dangling), _) | (_, Some(Kind::Dangling)) => (),
+                (Some(Kind::Dir), Some(Kind::Dir)) if options.recursive => {
+                    self.walk(first_path.as_deref(), second_path.as_deref(), &path)?
+                }
+                (Some(Kind::Dir), Some(Kind::Dir)) => self.entries.push(DirEntry::CommonDirs(path)),
+                (Some(Kind::File), Some(Kind::File)) => self.entries.push(DirEntry::Files(path)),
+                (Some(_), Some(_)) => self.entries.push(DirEntry::Mismatch(path)),
+                (Some(Kind::Dir), None) if options.new_file => self.walk(first_path.as_deref(), None, &path)?,
+                (Some(Kind::File), None) if options.new_file => self.entries.push(DirEntry::DeletedFile(path)),
+                (None, Some(Kind::Dir)) if options.new_file => self.walk(None, second_path.as_deref(), &path)?,
+                (None, Some(Kind::File)) if options.new_file => self.entries.push(DirEntry::AddedFile(path)),
+                (Some(_), None) => self.entries.push(DirEntry::OnlyInFirst(path)),
+                (None, Some(_)) => self.entries.push(DirEntry::OnlyInSecond(path)),
+                (None, None) => (),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns whether the `name` matches the shell wildcard `pattern`
+pub fn matches_pattern(pattern: &str, name: &str) -> bool {
+    let (pattern, name): (Vec<_>, Vec<_>) = (pattern.chars().collect(), name.chars().collect());
+    matches_from(&pattern, &name)
+}
+
+fn matches_from(pattern: &[char], name: &[char]) -> bool {
+    match pattern.first() {
+        None => name.is_empty(),
+        Some('*') => (0..=name.len()).any(|skipped| matches_from(&pattern[1..], &name[skipped..])),
+        Some('?') => !name.is_empty() && matches_from(&pattern[1..], &name[1..]),
+        Some('[') => match (pattern.iter().skip(2).position(|c| *c == ']'), name.first()) {
+            (Some(end), Some(c)) => {
+                let end = end + 2;
+                let (negated, class) = match pattern[1] {
+                    '!' | '^' => (true, &pattern[2..end]),
+                    _ => (false, &pattern[1..end]),
+                };
+                let mut is_matched = false;
+                let mut index = 0;
+                while index < class.len() {
+                    if index + 2 < class.len() && class[index + 1] == '-' {
+                        is_matched |= (class[index]..=class[index + 2]).contains(c);
+                        index += 3;
+                    } else {
+                        is_matched |= class[index] == *c;
+                        index += 1;
+                    }
+                }
+                is_matched != negated && matches_from(&pattern[end + 1..], &name[1..])
+            }
+            (None, Some(c)) => *c == '[' && matches_from(&pattern[1..], &name[1..]),
+            (_, None) => false,
+        },
+        Some(c) => name.first() == Some(c) && matches_from(&pattern[1..], &name[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    #[test]
+    fn test_matches_pattern() {
+        assert!(matches_pattern("*.o", "main.o"));
+        assert!(!matches_pattern("*.o", "main.rs"));
+        assert!(matches_pattern("target", "target"));
+        assert!(matches_pattern("?.txt", "a.txt"));
+        assert!(!matches_pattern("?.txt", "ab.txt"));
+        assert!(matches_pattern("*.[ch]", "lib.h"));
+        assert!(matches_pattern("file[0-9]", "file7"));
+        assert!(!matches_pattern("file[!0-9]", "file7"));
+        assert!(matches_pattern("[", "["));
+        assert!(matches_pattern("*", ""));
+    }
+
+    #[test]
+    fn test_compare_dirs() -> io::Result<()> {
+        let root = env::temp_dir().join(format!("ng-diff-dir-test-{}", std::process::id()));
+        let (first, second) = (root.join("a"), root.join("b"));
+        for dir in [&first, &second] {
+            fs::create_dir_all(dir.join("common"))?;
+            fs::write(dir.join("same.txt"), "same\n")?;
+            fs::write(dir.join("common").join("changed.txt"), dir.to_string_lossy().as_bytes())?;
+            fs::write(dir.join("build.o"), "")?;
+        }
+        fs::create_dir_all(first.join("old").join("nested"))?;
+        fs::write(first.join("old").join("nested").join("file.txt"), "old\n")?;
+        fs::write(second.join("new.txt"), "new\n")?;
+        fs::write(first.join("kind"), "file\n")?;
+        fs::create_dir_all(second.join("kind"))?;
+
+        let mut options = DirOptions {
+            exclude: vec!["*.o".to_string()],
+            ..DirOptions::default()
+        };
+        assert_eq!(
+            compare_dirs(&first, &second, &options)?,
+            vec![
+                DirEntry::CommonDirs(PathBuf::from("common")),
+                DirEntry::Mismatch(PathBuf::from("kind")),
+                DirEntry::OnlyInSecond(PathBuf::from("new.txt")),
+                DirEntry::OnlyInFirst(PathBuf::from("old")),
+                DirEntry::Files(PathBuf::from("same.txt")),
+            ]
+        );
+
+        options.recursive = true;
+        options.new_file = true;
+        assert_eq!(
+            compare_dirs(&first, &second, &options)?,
+            vec![
+                DirEntry::Files(Path::new("common").join("changed.txt")),
+                DirEntry::Mismatch(PathBuf::from("kind")),
+                DirEntry::AddedFile(PathBuf::from("new.txt")),
+                DirEntry::DeletedFile(Path::new("old").join("nested").join("file.txt")),
+                DirEntry::Files(PathBuf::from("same.txt")),
+            ]
+        );
+
+        assert_eq!(
+            DirEntry::Files(PathBuf::from("same.txt")).read(&first, &second)?,
+            Some((b"same\n".to_vec(), b"same\n".to_vec()))
+        );
+        assert_eq!(
+            DirEntry::AddedFile(PathBuf::from("new.txt")).read(&first, &second)?,
+            Some((Vec::new(), b"new\n".to_vec()))
+        );
+        assert_eq!(DirEntry::Mismatch(PathBuf::from("kind")).read(&first, &second)?, None);
+
+        fs::remove_dir_all(&root)
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_compare_dirs_symlinks() -> io::Result<()> {
+        use std::os::unix::fs::symlink;
+
+        let root = env::temp_dir().join(format!("ng-diff-dir-symlink-test-{}", std::process::id()));
+        let (first, second) = (root.join("a"), root.join("b"));
+        for dir in [&first, &second] {
+            fs::create_dir_all(dir.join("sub"))?;
+            fs::write(dir.join("file.txt"), "file\n")?;
+            symlink("file.txt", dir.join("link.txt"))?;
+        }
+        symlink("..", first.join("sub").join("loop"))?;
+        fs::create_dir_all(second.join("sub").join("loop"))?;
+        symlink("missing.txt", first.join("dangling.txt"))?;
+        fs::write(second.join("dangling.txt"), "")?;
+
+        let options = DirOptions {
+            recursive: true,
+            ..DirOptions::default()
+        };
+        assert_eq!(
+            compare_dirs(&first, &second, &options)?,
+            vec![
+                DirEntry::DanglingInFirst(PathBuf::from("dangling.txt")),
+                DirEntry::Files(PathBuf::from("file.txt")),
+                DirEntry::Files(PathBuf::from("link.txt")),
+                DirEntry::LoopInFirst(Path::new("sub").join("loop")),
+            ]
+        );
+
+        fs::remove_dir_all(&root)
+    }
+}
@@ -0,0 +1,299 @@
+//! The edit distances between sequences: Levenshtein distance (insertions,
+//! deletions and substitutions), optimal string alignment distance, which
+//! also counts transpositions of adjacent items, and the unrestricted
+//! Damerau–Levenshtein distance.
+//!
+//! The minimum of a distance matrix row never decreases from row to row,
+//! so the bounded versions stop as soon as a whole row exceeds the maximum.
+
+use std::{collections::HashMap, hash::Hash};
+
+use crate::NwScoreLine;
+
+/// This function returns the last line of the Levenshtein distance matrix,
+/// computed in linear space like `score_last_line`
+pub fn levenshtein_last_line<SeqA, SeqB, Line>(a: SeqA, b: SeqB) -> Line
+where
+    SeqA: IntoIterator,
+    SeqB: IntoIterator,
+    SeqA::IntoIter: ExactSizeIterator,
+    SeqB::IntoIter: ExactSizeIterator + Clone,
+    SeqA::Item: PartialEq<SeqB::Item>,
+    Line: NwScoreLine,
+{
+    let mut line = Line::zeroed(0);
+    levenshtein_rows(a, b, usize::MAX, &mut line);
+    line
+}
+
+pub fn levenshtein<SeqA, SeqB>(a: SeqA, b: SeqB) -> usize
+where
+    SeqA: IntoIterator,
+    SeqB: IntoIterator,
+    SeqA::IntoIter: ExactSizeIterator,
+    SeqB::IntoIter: ExactSizeIterator + Clone,
+    SeqA::Item: PartialEq<SeqB::Item>,
+{
+    let line: Vec<usize> = levenshtein_last_line(a, b);
+    line[line.len() - 1]
+}
+
+/// Returns the Levenshtein distance if it does not exceed `max`
+pub fn bounded_levenshtein<SeqA, SeqB>(a: SeqA, b: SeqB, max: usize) -> Option<usize>
+where
+    SeqA: IntoIterator,
+    SeqB: IntoIterator,
+    SeqA::IntoIter: ExactSizeIterator,
+    SeqB::IntoIter: ExactSizeIterator + Clone,
+    SeqA::Item: PartialEq<SeqB::Item>,
+{
+    let mut line = Vec::new();
+    if levenshtein_rows(a, b, max, &mut line) {
+        Some(line[line.len() - 1]).filter(|distance| *distance <= max)
+    } else {
+        None
+    }
+}
+
+/// Fills the `line` with the last line of the distance matrix,
+/// returns `false` if some row has exceeded `max`
+fn levenshtein_rows<SeqA, SeqB, Line>(a: SeqA, b: SeqB, max: usize, line: &mut Line) -> bool
+where
+    SeqA: IntoIterator,
+    SeqB: IntoIterator,
+    SeqA::IntoIter: ExactSizeIterator,
+    SeqB::IntoIter: ExactSizeIterator + Clone,
+    SeqA::Item: PartialEq<SeqB::Item>,
+    Line: NwScoreLine,
+{
+    let (a, b) = (a.into_iter(), b.into_iter());
+    if a.len().max(b.len()) - a.len().min(b.len()) > max {
+        return false;
+    }
+
+    *line = Line::zeroed(b.len() + 1);
+    for j in 0..line.len() {
+        line[j] = j;
+    }
+
+    for (i, a) in a.enumerate() {
+        let mut diag = line[0];
+        line[0] = i + 1;
+        let mut row_min = line[0];
+        for (j, b) in b.clone().enumerate() {
+            let idx = j + 1;
+
+            let up = line[idx];
+            let last = if a == b { diag } else { 1 + diag.min(up).min(line[j]) };
+            diag = up;
+            line[idx] = last;
+            row_min = row_min.min(last);
+        }
+        if row_min > max {
+            return false;
+        }
+    }
+    true
+}
+
+/// Returns the optimal string alignment distance, that is the Levenshtein
+/// distance that also counts a transposition of two adjacent items as a single
+/// edit, but does not edit any substring more than once
+pub fn osa_distance<SeqA, SeqB>(a: SeqA, b: SeqB) -> usize
+where
+    SeqA: IntoIterator,
+    SeqB: IntoIterator,
+    SeqA::IntoIter: ExactSizeIterator,
+    SeqB::IntoIter: ExactSizeIterator + Clone,
+    SeqA::Item: PartialEq<SeqB::Item>,
+{
+    match bounded_osa_distance(a, b, usize::MAX) {
+        Some(distance) => distance,
+        None => unreachable!("the distance never exceeds usize::MAX"),
+    }
+}
+
+/// Returns the optimal string alignment distance if it does not exceed `max`
+pub fn bounded_osa_distance<SeqA, SeqB>(a: SeqA, b: SeqB, max: usize) -> Option<usize>
+where
+    SeqA: IntoIterator,
+    SeqB: IntoIterator,
+    SeqA::IntoIter: ExactSizeIterator,
+    SeqB::IntoIter: ExactSizeIterator + Clone,
+    SeqA::Item: PartialEq<SeqB::Item>,
+{
+    let (a, b) = (a.into_iter(), b.into_iter());
+    if a.len().max(b.len()) - a.len().min(b.len()) > max {
+        return None;
+    }
+
+    let mut before: Vec<usize> = vec![0; b.len() + 1];
+    let mut penult: Vec<usize> = (0..=b.len()).collect();
+    let mut last = vec![0; b.len() + 1];
+    let mut prev_a = None;
+
+    for (i, a) in a.enumerate() {
+        last[0] = i + 1;
+        let mut row_min = last[0];
+        let mut prev_b = None;
+        for (j, b) in b.clone().enumerate() {
+            let idx = j + 1;
+
+            let mut distance = if a == b {
+                penult[j]
+            } else {
+                1 + penult[j].min(penult[idx]).min(last[j])
+            };
+            if let (Some(prev_a), Some(prev_b)) = (&prev_a, &prev_b) {
+                if a == *prev_b && *prev_a == b {
+                    distance = distance.min(before[j - 1] + 1);
+                }
+            }
+            last[idx] = distance;
+            row_min = row_min.min(distance);
+            prev_b = Some(b);
+        }
+        if row_min > max {
+            return None;
+        }
+
+        prev_a = Some(a);
+        before.copy_from_slice(&penult);
+        penult.copy_from_slice(&last);
+    }
+    Some(penult[b.len()]).filter(|distance| *distance <= max)
+}
+
+/// Returns the unrestricted Damerau–Levenshtein distance, which allows to edit
+/// the transposed items again. Unlike other distances it needs the whole
+/// distance matrix, so it takes O(mn) space.
+pub fn damerau_levenshtein<SeqA, SeqB>(a: SeqA, b: SeqB) -> usize
+where
+    SeqA: IntoIterator,
+    SeqB: IntoIterator<Item = SeqA::Item>,
+    SeqA::Item: Eq + Hash,
+{
+    match bounded_damerau_levenshtein(a, b, usize::MAX) {
+        Some(distance) => distance,
+        None => unreachable!("the distance never exceeds usize::MAX"),
+    }
+}
+
+/// Returns the unrestricted Damerau–Levenshtein distance if it does not exceed `max`
+pub fn bounded_damerau_levenshtein<SeqA, SeqB>(a: SeqA, b: SeqB, max: usize) -> Option<usize>
+where
+    SeqA: IntoIterator,
+    SeqB: IntoIterator<Item = SeqA::Item>,
+    SeqA::Item: Eq + Hash,
+{
+    let (a, b): (Vec<_>, Vec<_>) = (a.into_iter().collect(), b.into_iter().collect());
+    let (a_len, b_len) = (a.len(), b.len());
+    if a_len.max(b_len) - a_len.min(b_len) > max {
+        return None;
+    }
+
+    // The matrix is shifted by one row and one column, which hold the infinite distance
+    let width = b_len + 2;
+    let infinity = a_len + b_len;
+    let mut matrix = vec![infinity; (a_len + 2) * width];
+    for i in 0..=a_len {
+        matrix[(i + 1) * width + 1] = i;
+    }
+    for j in 0..=b_len {
+        matrix[width + j + 1] = j;
+    }
+
+    let mut last_rows = HashMap::new();
+    for (i, a_item) in a.iter().enumerate().map(|(i, item)| (i + 1, item)) {
+        let mut last_col = 0;
+        let mut row_min = i;
+        for (j, b_item) in b.iter().enumerate().map(|(j, item)| (j + 1, item)) {
+            let k = last_rows.get(b_item).copied().unwrap_or(0);
+            let l = last_col;
+            let cost = if a_item == b_item {
+                last_col = j;
+                0
+            } else {
+                1
+            };
+
+            let distance = (matrix[i * width + j] + cost)
+                .min(matrix[(i + 1) * width + j] + 1)
+                .min(matrix[i * width + j + 1] + 1)
+                .min(matrix[k * width + l] + (i - k - 1) + 1 + (j - l - 1));
+            matrix[(i + 1) * width + j + 1] = distance;
+            row_min = row_min.min(distance);
+        }
+        if row_min > max {
+            return None;
+        }
+        last_rows.insert(a_item, i);
+    }
+    Some(matrix[(a_len + 1) * width + b_len + 1]).filter(|distance| *distance <= max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AsCharsIter;
+
+    #[test]
+    fn test_levenshtein() {
+        assert_eq!(levenshtein(b"", b""), 0);
+        assert_eq!(levenshtein(b"abc", b""), 3);
+        assert_eq!(levenshtein(b"", b"abc"), 3);
+        assert_eq!(levenshtein("kitten".chars_iter(), "sitting".chars_iter()), 3);
+        assert_eq!(levenshtein("flaw".chars_iter(), "lawn".chars_iter()), 2);
+        assert_eq!(levenshtein("abc".chars_iter(), "acb".chars_iter()), 2);
+
+        let line: Vec<_> = levenshtein_last_line(b"kitten", b"sitting");
+        assert_eq!(line, vec![6, 6, 5, 4, 3, 3, 2, 3]);
+
+        assert_eq!(
+            bounded_levenshtein("kitten".chars_iter(), "sitting".chars_iter(), 3),
+            Some(3)
+        );
+        assert_eq!(
+            bounded_levenshtein("kitten".chars_iter(), "sitting".chars_iter(), 2),
+            None
+        );
+        assert_eq!(
+            bounded_levenshtein("kitten".chars_iter(), "xxxxxxxxxxxx".chars_iter(), 5),
+            None
+        );
+        assert_eq!(bounded_levenshtein(b"", b"", 0), Some(0));
+    }
+
+    #[test]
+    fn test_osa_distance() {
+        assert_eq!(osa_distance(b"", b""), 0);
+        assert_eq!(osa_distance(b"abc", b""), 3);
+        assert_eq!(osa_distance("abc".chars_iter(), "acb".chars_iter()), 1);
+        assert_eq!(osa_distance("ca".chars_iter(), "abc".chars_iter()), 3);
+        assert_eq!(osa_distance("kitten".chars_iter(), "sitting".chars_iter()), 3);
+        assert_eq!(osa_distance("abcdef".chars_iter(), "badcfe".chars_iter()), 3);
+
+        assert_eq!(
+            bounded_osa_distance("abcdef".chars_iter(), "badcfe".chars_iter(), 3),
+            Some(3)
+        );
+        assert_eq!(
+            bounded_osa_distance("abcdef".chars_iter(), "badcfe".chars_iter(), 2),
+            None
+        );
+    }
+
+    #[test]
+    fn test_damerau_levenshtein() {
+        assert_eq!(damerau_levenshtein(b"", b""), 0);
+        assert_eq!(damerau_levenshtein(b"abc", b""), 3);
+        assert_eq!(damerau_levenshtein(b"", b"abc"), 3);
+        assert_eq!(damerau_levenshtein("abc".chars(), "acb".chars()), 1);
+        assert_eq!(damerau_levenshtein("ca".chars(), "abc".chars()), 2);
+        assert_eq!(damerau_levenshtein("kitten".chars(), "sitting".chars()), 3);
+        assert_eq!(damerau_levenshtein("abcdef".chars(), "badcfe".chars()), 3);
+
+        assert_eq!(bounded_damerau_levenshtein("ca".chars(), "abc".chars(), 2), Some(2));
+        assert_eq!(bounded_damerau_levenshtein("ca".chars(), "abc".chars(), 1), None);
+    }
+}
@@ -0,0 +1,598 @@
+//! The textual formats of diffs: the normal, unified and context formats
+//! of GNU diff, a side-by-side view, an inline markup of word or char diffs
+//! and JSON. The formatters take the items of both sequences and the `DiffOp`s
+//! between them. The lines are expected to keep their terminators,
+//! as `lines` splits them, so a missing newline at the end of a file
+//! is reported like GNU diff does.
+//!
+//! ```
+//! use ng_diff::{diff_ops, format, Hirschberg, HirschbergAlg};
+//!
+//! let (first, second) = (format::lines("a\nb\nc\n"), format::lines("a\nc\nd\n"));
+//! let diff: Vec<_> = Hirschberg::diff(first.iter(), second.iter());
+//! let ops = diff_ops(&diff);
+//! assert_eq!(format::normal(&first, &second, &ops), "2d1\n< b\n3a3\n> d\n");
+//! ```
+
+use std::{iter, mem, ops::Range};
+
+use unicode_width::UnicodeWidthChar;
+
+use crate::{hunks, DiffOp, DiffTag};
+
+pub(crate) const NO_NEWLINE: &str = "\\ No newline at end of file\n";
+const TAB_WIDTH: usize = 8;
+
+/// Splits the `text` into the lines that keep their `\n` terminators
+pub fn lines(text: &str) -> Vec<&str> {
+    text.split_inclusive('\n').collect()
+}
+
+/// Splits the `text` into the words, the runs of whitespace
+/// and the single other chars
+pub fn words(text: &str) -> Vec<&str> {
+    let class = |c: char| {
+        if c.is_alphanumeric() || c == '_' {
+            0
+        } else if c.is_whitespace() {
+            1
+        } else {
+            2
+        }
+    };
+
+    let mut words = Vec::new();
+    let mut start = 0;
+    let mut prev_class = None;
+    for (index, c) in text.char_indices() {
+        let class = class(c);
+        if index > start && (prev_class != Some(class) || class == 2) {
+            words.push(&text[start..index]);
+            start = index;
+        }
+        prev_class = Some(class);
+    }
+    if start < text.len() {
+        words.push(&text[start..]);
+    }
+    words
+}
+
+/// Splits the `text` into the chars as string slices
+pub fn chars(text: &str) -> Vec<&str> {
+    text.char_indices()
+        .map(|(index, c)| &text[index..index + c.len_utf8()])
+        .collect()
+}
+
+/// Formats the diff like the default output of GNU diff, e.g. `2,3c2`
+/// followed by the `<` deleted and the `>` inserted lines
+pub fn normal<T: AsRef<str>>(first: &[T], second: &[T], ops: &[DiffOp]) -> String {
+    let mut out = String::new();
+    for op in ops {
+        match op.tag {
+            DiffTag::Equal => continue,
+            DiffTag::Delete => {
+                out += &format!("{}d{}\n", normal_range(&op.first), op.second.start);
+            }
+            DiffTag::Insert => {
+                out += &format!("{}a{}\n", op.first.start, normal_range(&op.second));
+            }
+            DiffTag::Replace => {
+                out += &format!("{}c{}\n", normal_range(&op.first), normal_range(&op.second));
+            }
+        }
+        for line in &first[op.first.clone()] {
+            push_line(&mut out, "< ", line.as_ref());
+        }
+        if op.tag == DiffTag::Replace {
+            out += "---\n";
+        }
+        for line in &second[op.second.clone()] {
+            push_line(&mut out, "> ", line.as_ref());
+        }
+    }
+    out
+}
+
+/// Formats the diff in the unified format with `context_len` equal lines
+/// around the changes and the `labels` of the files in the header
+pub fn unified<T: AsRef<str>>(
+    first: &[T], second: &[T], ops: &[DiffOp], labels: (&str, &str), context_len: usize,
+) -> String {
+    let hunks = hunks(ops, context_len);
+    if hunks.is_empty() {
+        return String::new();
+    }
+
+    let mut out = format!("--- {}\n+++ {}\n", labels.0, labels.1);
+    for hunk in hunks {
+        out += &format!(
+            "@@ -{} +{} @@\n",
+            unified_range(&hunk.first),
+            unified_range(&hunk.second)
+        );
+        for op in &hunk.ops {
+            if op.tag == DiffTag::Equal {
+                for line in &first[op.first.clone()] {
+                    push_line(&mut out, " ", line.as_ref());
+                }
+                continue;
+            }
+            for line in &first[op.first.clone()] {
+                push_line(&mut out, "-", line.as_ref());
+            }
+            for line in &second[op.second.clone()] {
+                push_line(&mut out, "+", line.as_ref());
+            }
+        }
+    }
+    out
+}
+
+/// Formats the diff in the context format with `context_len` equal lines
+/// around the changes and the `labels` of the files in the header
+pub fn context<T: AsRef<str>>(
+    first: &[T], second: &[T], ops: &[DiffOp], labels: (&str, &str), context_len: usize,
+) -> String {
+    let hunks = hunks(ops, context_len);
+    if hunks.is_empty() {
+        return String::new();
+    }
+
+    let mut out = format!("*** {}\n--- {}\n", labels.0, labels.1);
+    for hunk in hunks {
+        out += "***************\n";
+        out += &format!("*** {} ****\n", context_range(&hunk.first));
+        if hunk
+            .ops
+            .iter()
+            .any(|op| !op.first.is_empty() && op.tag != DiffTag::Equal)
+        {
+            for op in &hunk.ops {
+                let prefix = match op.tag {
+                    DiffTag::Equal => "  ",
+                    DiffTag::Delete => "- ",
+                    DiffTag::Replace => "! ",
+                    DiffTag::Insert => continue,
+                };
+                for line in &first[op.first.clone()] {
+                    push_line(&mut out, prefix, line.as_ref());
+                }
+            }
+        }
+
+        out += &format!("--- {} ----\n", context_range(&hunk.second));
+        if hunk
+            .ops
+            .iter()
+            .any(|op| !op.second.is_empty() && op.tag != DiffTag::Equal)
+        {
+            for op in &hunk.ops {
+                let prefix = match op.tag {
+                    DiffTag::Equal => "  ",
+                    DiffTag::Insert => "+ ",
+                    DiffTag::Replace => "! ",
+                    DiffTag::Delete => continue,
+                };
+                for line in &second[op.second.clone()] {
+                    push_line(&mut out, prefix, line.as_ref());
+                }
+            }
+        }
+    }
+    out
+}
+
+/// The options of `side_by_side`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SideBySideOptions {
+    /// The total number of the display columns, including the gutter
+    /// of 3 columns with the marker between the sides
+    pub width: usize,
+    /// Whether to wrap the lines that do not fit into their side
+    /// instead of truncating them
+    pub wrap: bool,
+    /// Whether to omit the equal lines, like `--suppress-common-lines`
+    pub suppress_common: bool,
+}
+
+impl SideBySideOptions {
+    pub fn new(width: usize) -> Self {
+        Self {
+            width,
+            wrap: false,
+            suppress_common: false,
+        }
+    }
+}
+
+/// Formats the diff in two columns that fit into the width, marking the changed
+/// rows with `|`, the deleted ones with `<` and the inserted ones with `>`.
+/// The deleted and inserted lines of a replacement share the rows. The width
+/// of the text is measured in the display columns of its chars, so the wide
+/// CJK chars take two columns, and the tabs are expanded to the stops of 8.
+pub fn side_by_side<T: AsRef<str>>(first: &[T], second: &[T], ops: &[DiffOp], options: &SideBySideOptions) -> String {
+    side_by_side_with(
+        first,
+        second,
+        ops,
+        options,
+        |left, _, right| {
+            (
+                vec![((), left.unwrap_or_default())],
+                (),
+                vec![((), right.unwrap_or_default())],
+            )
+        },
+        |_, text| text.to_string(),
+    )
+}
+
+/// Lays out the side-by-side view of the styled spans. The `spans` callback
+/// splits the lines of a row without their terminators into the spans
+/// and styles the marker of the row, the `paint` callback renders a span.
+pub(crate) fn side_by_side_with<T, S, F, P>(
+    first: &[T], second: &[T], ops: &[DiffOp], options: &SideBySideOptions, mut spans: F, paint: P,
+) -> String
+where
+    T: AsRef<str>,
+    S: Copy + PartialEq,
+    F: for<'a> FnMut(Option<&'a str>, char, Option<&'a str>) -> (Vec<(S, &'a str)>, S, Vec<(S, &'a str)>),
+    P: Fn(S, &str) -> String,
+{
+    let column = options.width.saturating_sub(3) / 2;
+    let mut out = String::new();
+    let mut push_row = |left: Option<&str>, marker: char, right: Option<&str>| {
+        let (left, marker_style, right) = spans(left.map(trim_newline), marker, right.map(trim_newline));
+        let (left, right) = (fit(&left, column), fit(&right, column));
+        let rows = if options.wrap { left.len().max(right.len()) } else { 1 };
+
+        for row in 0..rows {
+            let (left, left_width) = left
+                .get(row)
+                .map_or((&[][..], 0), |(spans, width)| (&spans[..], *width));
+            let right = right.get(row).map_or(&[][..], |(spans, _)| &spans[..]);
+            let mut row = String::new();
+            for (style, text) in left {
+                row += &paint(*style, text);
+            }
+            row.extend(iter::repeat(' ').take(column.saturating_sub(left_width) + 1));
+            row += &paint(marker_style, marker.encode_utf8(&mut [0; 4]));
+            row.push(' ');
+            for (style, text) in right {
+                row += &paint(*style, text);
+            }
+            out += row.trim_end();
+            out.push('\n');
+        }
+    };
+
+    for op in ops {
+        if op.tag == DiffTag::Equal && options.suppress_common {
+            continue;
+        }
+        let (left, right) = (&first[op.first.clone()], &second[op.second.clone()]);
+        for row in 0..left.len().max(right.len()) {
+            let (left, right) = (left.get(row).map(T::as_ref), right.get(row).map(T::as_ref));
+            let marker = match (op.tag, left, right) {
+                (DiffTag::Equal, ..) => ' ',
+                (_, Some(_), Some(_)) => '|',
+                (_, Some(_), None) => '<',
+                _ => '>',
+            };
+            push_row(left, marker, right);
+        }
+    }
+    out
+}
+
+/// Splits the spans into the pieces of at most `column` display columns
+/// with their widths, there is at least one piece
+fn fit<S: Copy + PartialEq>(spans: &[(S, &str)], column: usize) -> Vec<(Vec<(S, String)>, usize)> {
+    let char_width = |c: char, width: usize| match c {
+        '\t' => (TAB_WIDTH - width % TAB_WIDTH).min(column.saturating_sub(width)),
+        c => c.width().unwrap_or(0),
+    };
+
+    let mut pieces = Vec::new();
+    let (mut piece, mut width): (Vec<(S, String)>, _) = (Vec::new(), 0);
+    for (style, text) in spans {
+        for c in text.chars() {
+            if width > 0 && (width + char_width(c, width) > column || (c == '\t' && width >= column)) {
+                pieces.push((mem::take(&mut piece), width));
+                width = 0;
+            }
+            if !matches!(piece.last(), Some((last, _)) if last == style) {
+                piece.push((*style, String::new()));
+            }
+            let (_, text) = piece.last_mut().expect("there is a span");
+            let c_width = char_width(c, width);
+            if c == '\t' {
+                text.extend(iter::repeat(' ').take(c_width));
+            } else {
+                text.push(c);
+            }
+            width += c_width;
+        }
+    }
+    pieces.push((piece, width));
+    pieces
+}
+
+/// Formats the diff of the words or chars as the text with the deleted items
+/// enclosed in `[-` and `-]` and the inserted ones in `{+` and `+}`
+pub fn inline<T: AsRef<str>>(first: &[T], second: &[T], ops: &[DiffOp]) -> String {
+    let mut out = String::new();
+    for op in ops {
+        let (deleted, inserted) = (&first[op.first.clone()], &second[op.second.clone()]);
+        if op.tag == DiffTag::Equal {
+            deleted.iter().for_each(|item| out += item.as_ref());
+            continue;
+        }
+        if !deleted.is_empty() {
+            out += "[-";
+            deleted.iter().for_each(|item| out += item.as_ref());
+            out += "-]";
+        }
+        if !inserted.is_empty() {
+            out += "{+";
+            inserted.iter().for_each(|item| out += item.as_ref());
+            out += "+}";
+        }
+    }
+    out
+}
+
+/// Formats the report of the different binary files like GNU diff
+pub fn binary(labels: (&str, &str)) -> String {
+    format!("Binary files {} and {} differ\n", labels.0, labels.1)
+}
+
+/// Formats the diff as a JSON object with the `labels` of the files
+/// and the ops, e.g. `{"op":"insert","old":[3,3],"new":[3,5],"inserted":[...]}`,
+/// where the ranges are zero-based and half-open
+pub fn json<T: AsRef<str>>(first: &[T], second: &[T], ops: &[DiffOp], labels: (&str, &str)) -> String {
+    let mut out = format!(
+        "{{\"old\":{},\"new\":{},\"ops\":[",
+        json_string(labels.0),
+        json_string(labels.1)
+    );
+    for (index, op) in ops.iter().enumerate() {
+        if index > 0 {
+            out.push(',');
+        }
+        out += &format!(
+            "{{\"op\":\"{}\",\"old\":[{},{}],\"new\":[{},{}]",
+            op.tag.as_str(),
+            op.first.start,
+            op.first.end,
+            op.second.start,
+            op.second.end
+        );
+        if op.tag != DiffTag::Equal {
+            for (key, items) in [
+                ("deleted", &first[op.first.clone()]),
+                ("inserted", &second[op.second.clone()]),
+            ] {
+                if !items.is_empty() {
+                    let items: Vec<_> = items.iter().map(|item| json_string(item.as_ref())).collect();
+                    out += &format!(",\"{}\":[{}]", key, items.join(","));
+                }
+            }
+        }
+        out.push('}');
+    }
+    out += "]}\n";
+    out
+}
+
+pub(crate) fn push_line(out: &mut String, prefix: &str, line: &str) {
+    out.push_str(prefix);
+    out.push_str(line);
+    if !line.ends_with('\n') {
+        out.push('\n');
+        out.push_str(NO_NEWLINE);
+    }
+}
+
+fn trim_newline(line: &str) -> &str {
+    line.strip_suffix('\n')
+        .map(|line| line.strip_suffix('\r').unwrap_or(line))
+        .unwrap_or(line)
+}
+
+fn normal_range(range: &Range<usize>) -> String {
+    if range.len() == 1 {
+        format!("{}", range.end)
+    } else {
+        format!("{},{}", range.start + 1, range.end)
+    }
+}
+
+pub(crate) fn unified_range(range: &Range<usize>) -> String {
+    match range.len() {
+        0 => format!("{},0", range.start),
+        1 => format!("{}", range.end),
+        len => format!("{},{}", range.start + 1, len),
+    }
+}
+
+fn context_range(range: &Range<usize>) -> String {
+    if range.len() <= 1 {
+        format!("{}", range.end)
+    } else {
+        format!("{},{}", range.start + 1, range.end)
+    }
+}
+
+fn json_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out += "\\\"",
+            '\\' => out += "\\\\",
+            '\n' => out += "\\n",
+            '\r' => out += "\\r",
+            '\t' => out += "\\t",
+            c if (c as u32) < 0x20 => out += &format!("\\u{:04x}", c as u32),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{diff_ops, Hirschberg, HirschbergAlg};
+
+    const FIRST: &str = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\n";
+    const SECOND: &str = "a\nB\nc\nd\ne\nf\ng\nh\ni\nk\nl";
+
+    fn line_ops(first: &[&str], second: &[&str]) -> Vec<DiffOp> {
+        let diff: Vec<_> = Hirschberg::diff(first.iter(), second.iter());
+        diff_ops(&diff)
+    }
+
+    #[test]
+    fn test_split() {
+        assert_eq!(lines("a\nb\r\n\nc"), vec!["a\n", "b\r\n", "\n", "c"]);
+        assert_eq!(lines(""), Vec::<&str>::new());
+        assert_eq!(words("fn main() {}"), vec!["fn", " ", "main", "(", ")", " ", "{", "}"]);
+        assert_eq!(words("a_b  ÿ"), vec!["a_b", "  ", "ÿ"]);
+        assert_eq!(chars("aÿ"), vec!["a", "ÿ"]);
+    }
+
+    #[test]
+    fn test_normal() {
+        let (first, second) = (lines(FIRST), lines(SECOND));
+        assert_eq!(
+            normal(&first, &second, &line_ops(&first, &second)),
+            "2c2\n< b\n---\n> B\n10d9\n< j\n11a11\n> l\n\\ No newline at end of file\n"
+        );
+
+        let (first, second) = (lines("a\nb\nc\n"), lines("x\n"));
+        assert_eq!(
+            normal(&first, &second, &line_ops(&first, &second)),
+            "1,3c1\n< a\n< b\n< c\n---\n> x\n"
+        );
+    }
+
+    #[test]
+    fn test_unified() {
+        let (first, second) = (lines(FIRST), lines(SECOND));
+        let ops = line_ops(&first, &second);
+        assert_eq!(
+            unified(&first, &second, &ops, ("a.txt", "b.txt"), 3),
+            "--- a.txt\n+++ b.txt\n@@ -1,5 +1,5 @@\n a\n-b\n+B\n c\n d\n e\n@@ -7,5 +7,5 @@\n g\n h\n i\n-j\n k\n+l\n\\ \
+             No newline at end of file\n"
+        );
+        assert_eq!(
+            unified(&first, &second, &ops, ("a.txt", "b.txt"), 1),
+            "--- a.txt\n+++ b.txt\n@@ -1,3 +1,3 @@\n a\n-b\n+B\n c\n@@ -9,3 +9,3 @@\n i\n-j\n k\n+l\n\\ No newline at end of \
+             file\n"
+        );
+
+        let (first, second) = (lines(""), lines("x\n"));
+        assert_eq!(
+            unified(&first, &second, &line_ops(&first, &second), ("a", "b"), 3),
+            "--- a\n+++ b\n@@ -0,0 +1 @@\n+x\n"
+        );
+        assert_eq!(unified(&first, &first, &[], ("a", "b"), 3), "");
+    }
+
+    #[test]
+    fn test_context() {
+        let (first, second) = (lines("a\nb\nc\nd\n"), lines("a\nc\nd\ne\n"));
+        assert_eq!(
+            context(&first, &second, &line_ops(&first, &second), ("a", "b"), 1),
+            "*** a\n--- b\n***************\n*** 1,4 ****\n  a\n- b\n  c\n  d\n--- 1,4 ----\n  a\n  c\n  d\n+ e\n"
+        );
+
+        let (first, second) = (lines("a\nb\nc\nd\ne\nf\n"), lines("a\nc\nd\ne\nf\ng\n"));
+        assert_eq!(
+            context(&first, &second, &line_ops(&first, &second), ("a", "b"), 1),
+            "*** a\n--- b\n***************\n*** 1,3 ****\n  a\n- b\n  c\n--- 1,2 ----\n***************\n*** 6 ****\n--- 5,6 \
+             ----\n  f\n+ g\n"
+        );
+
+        let (first, second) = (lines("a\nb\n"), lines("a\nB\n"));
+        assert_eq!(
+            context(&first, &second, &line_ops(&first, &second), ("a", "b"), 3),
+            "*** a\n--- b\n***************\n*** 1,2 ****\n  a\n! b\n--- 1,2 ----\n  a\n! B\n"
+        );
+    }
+
+    #[test]
+    fn test_side_by_side() {
+        let (first, second) = (lines("a\nb\nc\n"), lines("a\nB\nC\nd\n"));
+        assert_eq!(
+            side_by_side(&first, &second, &line_ops(&first, &second), &SideBySideOptions::new(13)),
+            "a       a\nb     | B\nc     | C\n      > d\n"
+        );
+
+        let (first, second) = (lines("日本語テキスト\n"), lines("日本語\n"));
+        let ops = line_ops(&first, &second);
+        let mut options = SideBySideOptions::new(13);
+        assert_eq!(side_by_side(&first, &second, &ops, &options), "日本  | 日本\n");
+        options.wrap = true;
+        assert_eq!(
+            side_by_side(&first, &second, &ops, &options),
+            "日本  | 日本\n語テ  | 語\nキス  |\nト    |\n"
+        );
+
+        let (first, second) = (lines("x\na\tb\n"), lines("x\na\tc\ny\n"));
+        let mut options = SideBySideOptions::new(23);
+        options.suppress_common = true;
+        assert_eq!(
+            side_by_side(&first, &second, &line_ops(&first, &second), &options),
+            "a       b  | a       c\n           > y\n"
+        );
+    }
+
+    fn fit_text(text: &str, column: usize) -> Vec<(String, usize)> {
+        fit(&[((), text)], column)
+            .into_iter()
+            .map(|(spans, width)| (spans.into_iter().map(|(_, text)| text).collect(), width))
+            .collect()
+    }
+
+    #[test]
+    fn test_fit() {
+        assert_eq!(fit_text("", 4), vec![(String::new(), 0)]);
+        assert_eq!(fit_text("e\u{301}tat", 4), vec![("e\u{301}tat".to_string(), 4)]);
+        assert_eq!(
+            fit_text("ab\tc", 4),
+            vec![("ab  ".to_string(), 4), ("c".to_string(), 1)]
+        );
+        assert_eq!(
+            fit_text("abcd\tc", 4),
+            vec![("abcd".to_string(), 4), ("    ".to_string(), 4), ("c".to_string(), 1)]
+        );
+
+        let spans = fit(&[(1, "ab"), (2, "cd"), (1, "e")], 3);
+        assert_eq!(
+            spans,
+            vec![
+                (vec![(1, "ab".to_string()), (2, "c".to_string())], 3),
+                (vec![(2, "d".to_string()), (1, "e".to_string())], 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_inline_and_json() {
+        let (first, second) = (words("the quick fox"), words("the slow fox!"));
+        let ops = line_ops(&first, &second);
+        assert_eq!(inline(&first, &second, &ops), "the [-quick-]{+slow+} fox{+!+}");
+        assert_eq!(
+            json(&first, &second, &ops, ("a\"", "b")),
+            "{\"old\":\"a\\\"\",\"new\":\"b\",\"ops\":[{\"op\":\"equal\",\"old\":[0,2],\"new\":[0,2]},{\"op\":\"replace\",\
+             \"old\":[2,3],\"new\":[2,3],\"deleted\":[\"quick\"],\"inserted\":[\"slow\"]},{\"op\":\"equal\",\"old\":[3,5],\
+             \"new\":[3,5]},{\"op\":\"insert\",\"old\":[5,5],\"new\":[5,6],\"inserted\":[\"!\"]}]}\n"
+        );
+    }
+}
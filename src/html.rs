@@ -0,0 +1,377 @@
+//! The self-contained HTML page of a diff with the inline CSS and no scripts.
+//! The lines are numbered, the changed words or chars within the paired lines
+//! are highlighted, and the long runs of the equal lines are collapsed
+//! into the `<details>` elements that expand them on a click.
+//!
+//! ```
+//! use ng_diff::{diff_ops, format, html, Hirschberg, HirschbergAlg};
+//!
+//! let (first, second) = (format::lines("a < b\n"), format::lines("a > b\n"));
+//! let diff: Vec<_> = Hirschberg::diff(first.iter(), second.iter());
+//! let page = html::html(&first, &second, &diff_ops(&diff), ("old", "new"), &html::HtmlOptions::default());
+//! assert!(page.contains("a <del>&lt;</del> b"));
+//! ```
+
+use std::fmt::Write;
+
+use crate::{color::refine, color::Emphasis, DiffOp, DiffTag};
+
+const STYLE: &str = "\
+body{font-family:sans-serif;margin:1em}\
+h1{font-size:1.2em}\
+table{border-collapse:collapse;table-layout:fixed;width:100%;font-family:monospace;font-size:13px}\
+td,th{padding:0 .4em;vertical-align:top}\
+th{background:#eee;text-align:left;font-family:sans-serif}\
+col.num{width:4em}\
+col.mark{width:1.5em}\
+td.num{color:#888;text-align:right;user-select:none;background:#f7f7f7}\
+td.mark{color:#888;user-select:none}\
+td.text{white-space:pre-wrap;word-break:break-all}\
+tr.del td.text,td.text.del{background:#ffebe9}\
+tr.ins td.text,td.text.ins{background:#e6ffec}\
+td.text.empty{background:#f7f7f7}\
+del{background:#ffc0c0;text-decoration:none}\
+ins{background:#abf2bc;text-decoration:none}\
+details>summary{cursor:pointer;color:#555;background:#f1f8ff;font-family:sans-serif;font-size:12px;padding:2px .4em}\
+p.same{font-family:sans-serif;color:#555}";
+
+/// The arrangement of the lines in the table
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HtmlLayout {
+    /// The old lines on the left and the new ones on the right
+    SideBySide,
+    /// The deleted lines followed by the inserted ones, like the unified format
+    Inline,
+}
+
+/// The options of `html`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HtmlOptions {
+    pub layout: HtmlLayout,
+    /// The number of the equal lines shown around the changes, the others
+    /// are collapsed. All the lines are shown when it is `None`.
+    pub context_len: Option<usize>,
+    pub emphasis: Emphasis,
+}
+
+impl Default for HtmlOptions {
+    fn default() -> Self {
+        Self {
+            layout: HtmlLayout::SideBySide,
+            context_len: Some(3),
+            emphasis: Emphasis::Words,
+        }
+    }
+}
+
+/// Renders the diff of the lines as an HTML page with the `labels` of the files
+pub fn html<T: AsRef<str>>(
+    first: &[T], second: &[T], ops: &[DiffOp], labels: (&str, &str), options: &HtmlOptions,
+) -> String {
+    let title = format!("{} vs {}", escape(labels.0), escape(labels.1));
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+         <style>{}</style>\n</head>\n<body>\n<h1>{}</h1>\n",
+        title, STYLE, title
+    );
+    if ops.iter().all(|op| op.tag == DiffTag::Equal) {
+        out += "<p class=\"same\">The files are identical.</p>\n";
+    }
+
+    let mut table = Table::new(options.layout, labels);
+    for (index, op) in ops.iter().enumerate() {
+        if op.tag != DiffTag::Equal {
+            table.push_changes(first, second, op, options.emphasis);
+            continue;
+        }
+
+        let len = op.first.len();
+        let (head, tail) = match options.context_len {
+            Some(context_len) => (
+                if index == 0 { 0 } else { context_len.min(len) },
+                if index + 1 == ops.len() {
+                    0
+                } else {
+                    context_len.min(len)
+                },
+            ),
+            None => (len, 0),
+        };
+        if head + tail >= len {
+            table.push_equal(first, op, 0..len);
+        } else {
+            table.push_equal(first, op, 0..head);
+            out += &table.finish();
+            out += &format!(
+                "<details>\n<summary>{} unchanged line{}</summary>\n",
+                len - head - tail,
+                if len - head - tail == 1 { "" } else { "s" }
+            );
+            table.push_equal(first, op, head..len - tail);
+            out += &table.finish();
+            out += "</details>\n";
+            table.push_equal(first, op, len - tail..len);
+        }
+    }
+    out += &table.finish();
+    out += "</body>\n</html>\n";
+    out
+}
+
+/// The rows of a table that is being rendered
+struct Table<'a> {
+    layout: HtmlLayout,
+    labels: Option<(&'a str, &'a str)>,
+    rows: String,
+}
+
+impl<'a> Table<'a> {
+    fn new(layout: HtmlLayout, labels: (&'a str, &'a str)) -> Self {
+        Self {
+            layout,
+            labels: Some(labels),
+            rows: String::new(),
+        }
+    }
+
+    /// Returns the table of the rows pushed so far, the first one has the header
+    fn finish(&mut self) -> String {
+        if self.rows.is_empty() {
+            return String::new();
+        }
+        let mut table = String::from("<table>\n");
+        let header = self.labels.take();
+        match self.layout {
+            HtmlLayout::SideBySide => {
+                table += "<colgroup><col class=\"num\"><col><col class=\"num\"><col></colgroup>\n";
+                if let Some((first, second)) = header {
+                    let _ = writeln!(
+                        table,
+                        "<thead><tr><th colspan=\"2\">{}</th><th colspan=\"2\">{}</th></tr></thead>",
+                        escape(first),
+                        escape(second)
+                    );
+                }
+            }
+            HtmlLayout::Inline => {
+                table += "<colgroup><col class=\"num\"><col class=\"num\"><col class=\"mark\"><col></colgroup>\n";
+                if let Some((first, second)) = header {
+                    let _ = writeln!(
+                        table,
+                        "<thead><tr><th colspan=\"4\">--- {}<br>+++ {}</th></tr></thead>",
+                        escape(first),
+                        escape(second)
+                    );
+                }
+            }
+        }
+        table += "<tbody>\n";
+        table += &std::mem::take(&mut self.rows);
+        table += "</tbody>\n</table>\n";
+        table
+    }
+
+    fn push_equal<T: AsRef<str>>(&mut self, first: &[T], op: &DiffOp, range: std::ops::Range<usize>) {
+        for offset in range {
+            let (i, j) = (op.first.start + offset, op.second.start + offset);
+            let text = escape(trim_newline(first[i].as_ref()));
+            let _ = match self.layout {
+                HtmlLayout::SideBySide => writeln!(
+                    self.rows,
+                    "<tr><td class=\"num\">{}</td><td class=\"text\">{}</td><td class=\"num\">{}</td>\
+                     <td class=\"text\">{}</td></tr>",
+                    i + 1,
+                    text,
+                    j + 1,
+                    text
+                ),
+                HtmlLayout::Inline => writeln!(
+                    self.rows,
+                    "<tr><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"mark\"></td>\
+                     <td class=\"text\">{}</td></tr>",
+                    i + 1,
+                    j + 1,
+                    text
+                ),
+            };
+        }
+    }
+
+    fn push_changes<T: AsRef<str>>(&mut self, first: &[T], second: &[T], op: &DiffOp, emphasis: Emphasis) {
+        let (deleted, inserted) = (&first[op.first.clone()], &second[op.second.clone()]);
+        let highlighted: Vec<_> = deleted
+            .iter()
+            .zip(inserted)
+            .map(|(deleted, inserted)| {
+                let (deleted, inserted) = (trim_newline(deleted.as_ref()), trim_newline(inserted.as_ref()));
+                match refine(deleted, inserted, emphasis) {
+                    Some((deleted_spans, inserted_spans)) => {
+                        (highlight(&deleted_spans, "del"), highlight(&inserted_spans, "ins"))
+                    }
+                    None => (escape(deleted), escape(inserted)),
+                }
+            })
+            .collect();
+        let text = |lines: &[T], index: usize, highlighted: Option<&String>| match highlighted {
+            Some(text) => text.clone(),
+            None => escape(trim_newline(lines[index].as_ref())),
+        };
+
+        match self.layout {
+            HtmlLayout::SideBySide => {
+                for row in 0..deleted.len().max(inserted.len()) {
+                    self.rows += "<tr>";
+                    for (lines, start, class, side) in [
+                        (deleted, op.first.start, "del", 0),
+                        (inserted, op.second.start, "ins", 1),
+                    ] {
+                        if row < lines.len() {
+                            let highlighted = highlighted
+                                .get(row)
+                                .map(|pair| if side == 0 { &pair.0 } else { &pair.1 });
+                            let _ = write!(
+                                self.rows,
+                                "<td class=\"num\">{}</td><td class=\"text {}\">{}</td>",
+                                start + row + 1,
+                                class,
+                                text(lines, row, highlighted)
+                            );
+                        } else {
+                            self.rows += "<td class=\"num\"></td><td class=\"text empty\"></td>";
+                        }
+                    }
+                    self.rows += "</tr>\n";
+                }
+            }
+            HtmlLayout::Inline => {
+                for row in 0..deleted.len() {
+                    let _ = writeln!(
+                        self.rows,
+                        "<tr class=\"del\"><td class=\"num\">{}</td><td class=\"num\"></td><td class=\"mark\">-</td>\
+                         <td class=\"text\">{}</td></tr>",
+                        op.first.start + row + 1,
+                        text(deleted, row, highlighted.get(row).map(|pair| &pair.0))
+                    );
+                }
+                for row in 0..inserted.len() {
+                    let _ = writeln!(
+                        self.rows,
+                        "<tr class=\"ins\"><td class=\"num\"></td><td class=\"num\">{}</td><td class=\"mark\">+</td>\
+                         <td class=\"text\">{}</td></tr>",
+                        op.second.start + row + 1,
+                        text(inserted, row, highlighted.get(row).map(|pair| &pair.1))
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Escapes the spans and wraps the changed ones into the `tag`
+fn highlight(spans: &[(bool, &str)], tag: &str) -> String {
+    let mut out = String::new();
+    for (is_changed, text) in spans {
+        if *is_changed {
+            let _ = write!(out, "<{}>{}</{}>", tag, escape(text), tag);
+        } else {
+            out += &escape(text);
+        }
+    }
+    out
+}
+
+fn trim_newline(line: &str) -> &str {
+    line.strip_suffix('\n')
+        .map(|line| line.strip_suffix('\r').unwrap_or(line))
+        .unwrap_or(line)
+}
+
+/// Escapes the text for the HTML content and the attribute values
+pub fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out += "&amp;",
+            '<' => out += "&lt;",
+            '>' => out += "&gt;",
+            '"' => out += "&quot;",
+            '\'' => out += "&#39;",
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{diff_ops, format, Hirschberg, HirschbergAlg};
+
+    fn line_ops(first: &[&str], second: &[&str]) -> Vec<DiffOp> {
+        let diff: Vec<_> = Hirschberg::diff(first.iter(), second.iter());
+        diff_ops(&diff)
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            escape("<a href=\"x\">'&'</a>"),
+            "&lt;a href=&quot;x&quot;&gt;&#39;&amp;&#39;&lt;/a&gt;"
+        );
+    }
+
+    #[test]
+    fn test_side_by_side() {
+        let first: Vec<_> = (1..=10).map(|i| format!("line {}\n", i)).collect();
+        let mut second = first.clone();
+        second[0] = "line <1>\n".to_string();
+        second.insert(9, "new\n".to_string());
+        let (first, second): (Vec<&str>, Vec<&str>) = (
+            first.iter().map(String::as_str).collect(),
+            second.iter().map(String::as_str).collect(),
+        );
+        let options = HtmlOptions {
+            context_len: Some(1),
+            ..HtmlOptions::default()
+        };
+        let page = html(&first, &second, &line_ops(&first, &second), ("a&b", "c"), &options);
+
+        assert!(page.starts_with("<!DOCTYPE html>\n"));
+        assert!(page.contains("<title>a&amp;b vs c</title>"));
+        assert!(page.contains("<thead><tr><th colspan=\"2\">a&amp;b</th><th colspan=\"2\">c</th></tr></thead>"));
+        assert!(page.contains(
+            "<tr><td class=\"num\">1</td><td class=\"text del\">line 1</td>\
+             <td class=\"num\">1</td><td class=\"text ins\">line <ins>&lt;</ins>1<ins>&gt;</ins></td></tr>"
+        ));
+        assert!(page.contains(
+            "<tr><td class=\"num\"></td><td class=\"text empty\"></td>\
+             <td class=\"num\">10</td><td class=\"text ins\">new</td></tr>"
+        ));
+        assert!(page.contains("<details>\n<summary>6 unchanged lines</summary>\n<table>"));
+        assert_eq!(page.matches("<table>").count(), 3);
+        assert_eq!(page.matches("<thead>").count(), 1);
+        assert!(page.ends_with("</table>\n</body>\n</html>\n"));
+    }
+
+    #[test]
+    fn test_inline() {
+        let (first, second) = (format::lines("a\nb\n"), format::lines("a\nc\n"));
+        let options = HtmlOptions {
+            layout: HtmlLayout::Inline,
+            context_len: None,
+            emphasis: Emphasis::None,
+        };
+        let page = html(&first, &second, &line_ops(&first, &second), ("x", "y"), &options);
+        assert!(page.contains(
+            "<tr><td class=\"num\">1</td><td class=\"num\">1</td><td class=\"mark\"></td><td class=\"text\">a</td></tr>\n\
+             <tr class=\"del\"><td class=\"num\">2</td><td class=\"num\"></td><td class=\"mark\">-</td>\
+             <td class=\"text\">b</td></tr>\n\
+             <tr class=\"ins\"><td class=\"num\"></td><td class=\"num\">2</td><td class=\"mark\">+</td>\
+             <td class=\"text\">c</td></tr>\n"
+        ));
+        assert!(!page.contains("<details>"));
+
+        let page = html(&first, &first, &line_ops(&first, &first), ("x", "x"), &options);
+        assert!(page.contains("The files are identical."));
+    }
+}
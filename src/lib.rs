@@ -7,10 +7,37 @@
 //! CiteSeerX 10.1.1.348.4774. doi:10.1145/360825.360861. MR 0375829.
 //! http://www.mathcs.emory.edu/~cheung/Courses/323/Syllabus/DynProg/Docs/Hirschberg=Linear-space-LCS.pdf
 
-pub use self::{chars::*, seq::*};
-
+use std::{marker::PhantomData, mem};
+
+pub use self::{
+    access::*, affine::*, align::*, binary::*, chars::*, cigar::*, cleanup::*, delta::*, distance::*, limit::*,
+    local::*, moves::*, ops::*, ratio::*, seq::*, slider::*,
+};
+
+pub mod bio;
+pub mod color;
+pub mod dir;
+pub mod format;
+pub mod html;
+pub mod lsp;
+pub mod vcdiff;
+
+mod access;
+mod affine;
+mod align;
+mod binary;
 mod chars;
+mod cigar;
+mod cleanup;
+mod delta;
+mod distance;
+mod limit;
+mod local;
+mod moves;
+mod ops;
+mod ratio;
 mod seq;
+mod slider;
 
 /// This function returns the last line of the Needleman-Wunsch score matrix
 pub fn score_last_line<SeqA, SeqB, Line>(a: SeqA, b: SeqB) -> Line
@@ -21,11 +48,41 @@ where
     SeqB::IntoIter: ExactSizeIterator + Clone,
     SeqA::Item: PartialEq<SeqB::Item>,
     Line: NwScoreLine,
+{
+    match try_score_last_line(a, b, &NoLimit) {
+        Ok(line) => line,
+        Err(Interrupted) => unreachable!("NoLimit is never exceeded"),
+    }
+}
+
+/// The number of the score matrix cells computed between the checks of a `Limit`
+const LIMIT_CHECK_CELLS: usize = 4096;
+
+/// Works like `score_last_line`, but stops with an error once the `limit`
+/// is exceeded
+pub fn try_score_last_line<SeqA, SeqB, Line, L>(a: SeqA, b: SeqB, limit: &L) -> Result<Line, Interrupted>
+where
+    SeqA: IntoIterator,
+    SeqB: IntoIterator,
+    SeqA::IntoIter: ExactSizeIterator,
+    SeqB::IntoIter: ExactSizeIterator + Clone,
+    SeqA::Item: PartialEq<SeqB::Item>,
+    Line: NwScoreLine,
+    L: Limit + ?Sized,
 {
     let (a, b) = (a.into_iter(), b.into_iter());
     let mut penult = Line::zeroed(b.len() + 1);
+    let mut unchecked_cells = 0;
 
     for a in a {
+        unchecked_cells += penult.len();
+        if unchecked_cells >= LIMIT_CHECK_CELLS {
+            if limit.is_exceeded() {
+                return Err(Interrupted);
+            }
+            unchecked_cells = 0;
+        }
+
         let mut prev_penult = 0;
         let mut prev_last = 0;
         for (j, b) in b.clone().enumerate() {
@@ -41,9 +98,16 @@ where
             penult[idx] = last;
         }
     }
-    penult
+    Ok(penult)
 }
 
+/// The cell budget (`a_len * b_len`) below which `HybridHirschberg` solves
+/// a sub-problem by a traceback over the full score matrix
+pub const DEFAULT_FULL_MATRIX_CELLS: usize = 4096;
+
+/// The sequences are split by `nth` and `nth_back`, which take O(1) time for
+/// slices, `Vec`s, `RandomAccessSeq`s like `CharBuf` and the `CharsIter`s of ASCII
+/// text, but walk the `CharsIter`s of any other text.
 pub fn hirschberg_diff<Line, SeqA, SeqB, SeqC>(a: SeqA, b: SeqB) -> SeqC
 where
     SeqA: IntoIterator,
@@ -54,13 +118,247 @@ where
     SeqC: Difference<SeqA::Item>,
     Line: NwScoreLine,
 {
-    fn hirschberg_diff_inner<Line, IterA, IterB, SeqC>(mut a: SeqIter<IterA>, b: SeqIter<IterB>, diff: &mut SeqC)
+    hybrid_hirschberg_diff::<Line, _, _, _>(a, b, 0)
+}
+
+/// Works like `hirschberg_diff`, but every sub-problem with less than
+/// `full_matrix_cells` cells is solved by filling its full score matrix and
+/// tracing back through it instead of halving it further. The matrix is bounded
+/// by the budget, so the memory stays linear in the length of the sequences.
+pub fn hybrid_hirschberg_diff<Line, SeqA, SeqB, SeqC>(a: SeqA, b: SeqB, full_matrix_cells: usize) -> SeqC
+where
+    SeqA: IntoIterator,
+    SeqA::Item: PartialEq,
+    SeqB: IntoIterator<Item = SeqA::Item>,
+    SeqA::IntoIter: SequenceIterator,
+    SeqB::IntoIter: SequenceIterator,
+    SeqC: Difference<SeqA::Item>,
+    Line: NwScoreLine,
+{
+    match limited_hirschberg_diff::<_, _, Unreplaced<SeqC>, _, _>(
+        a,
+        b,
+        &LcsScoring::<Line>(PhantomData),
+        full_matrix_cells,
+        &NoLimit,
+        false,
+    ) {
+        Ok(Bounded { value, .. }) => value.0,
+        Err(Interrupted) => unreachable!("NoLimit is never exceeded"),
+    }
+}
+
+/// Works like `hybrid_hirschberg_diff`, but stops with an error once
+/// the `limit` is exceeded, e.g. when a deadline `Instant` is reached
+/// or a cancellation `AtomicBool` is set.
+pub fn try_hirschberg_diff<Line, SeqA, SeqB, SeqC, L>(
+    a: SeqA, b: SeqB, full_matrix_cells: usize, limit: &L,
+) -> Result<SeqC, Interrupted>
+where
+    SeqA: IntoIterator,
+    SeqA::Item: PartialEq,
+    SeqB: IntoIterator<Item = SeqA::Item>,
+    SeqA::IntoIter: SequenceIterator,
+    SeqB::IntoIter: SequenceIterator,
+    SeqC: Difference<SeqA::Item>,
+    Line: NwScoreLine,
+    L: Limit + ?Sized,
+{
+    limited_hirschberg_diff::<_, _, Unreplaced<SeqC>, _, _>(
+        a,
+        b,
+        &LcsScoring::<Line>(PhantomData),
+        full_matrix_cells,
+        limit,
+        false,
+    )
+    .map(|diff| diff.value.0)
+}
+
+/// Works like `hybrid_hirschberg_diff`, but once the `limit` is exceeded, or
+/// a sub-problem does not fit into a `Budget`, the region that is not refined
+/// yet is only trimmed of its common prefix and suffix, and the rest of it is
+/// treated as deleted from `a` and inserted from `b`. The result reports
+/// whether the diff is still minimal.
+pub fn bounded_hirschberg_diff<Line, SeqA, SeqB, SeqC, L>(
+    a: SeqA, b: SeqB, full_matrix_cells: usize, limit: &L,
+) -> Bounded<SeqC>
+where
+    SeqA: IntoIterator,
+    SeqA::Item: PartialEq,
+    SeqB: IntoIterator<Item = SeqA::Item>,
+    SeqA::IntoIter: SequenceIterator,
+    SeqB::IntoIter: SequenceIterator,
+    SeqC: Difference<SeqA::Item>,
+    Line: NwScoreLine,
+    L: Limit + ?Sized,
+{
+    match limited_hirschberg_diff::<_, _, Unreplaced<SeqC>, _, _>(
+        a,
+        b,
+        &LcsScoring::<Line>(PhantomData),
+        full_matrix_cells,
+        limit,
+        true,
+    ) {
+        Ok(diff) => Bounded {
+            value: diff.value.0,
+            is_minimal: diff.is_minimal,
+        },
+        Err(Interrupted) => unreachable!("a degrading diff is never interrupted"),
+    }
+}
+
+struct Context<'a, L: ?Sized> {
+    full_matrix_cells: usize,
+    limit: &'a L,
+    degrade: bool,
+    is_minimal: bool,
+}
+
+impl<L: ?Sized> Context<'_, L> {
+    /// Degrades the diff of `a` and `b` to the common prefix and suffix around
+    /// a coarse replacement, or fails if the context does not degrade
+    fn interrupt<IterA, IterB, SeqC>(
+        &mut self, a: SeqIter<IterA>, b: SeqIter<IterB>, diff: &mut SeqC,
+    ) -> Result<(), Interrupted>
     where
         IterA: SequenceIterator,
         IterA::Item: PartialEq,
         IterB: SequenceIterator + Iterator<Item = IterA::Item>,
         SeqC: Difference<IterA::Item>,
-        Line: NwScoreLine,
+    {
+        if !self.degrade {
+            return Err(Interrupted);
+        }
+
+        let prefix = a.clone().zip(b.clone()).take_while(|(a, b)| a == b).count();
+        for b_item in b.clone().take(prefix) {
+            diff.push_both(b_item);
+        }
+        let (a, b) = (a.skip(prefix), b.skip(prefix));
+
+        let suffix = a.clone().rev().zip(b.clone().rev()).take_while(|(a, b)| a == b).count();
+        let (a_len, b_len) = (a.len() - suffix, b.len() - suffix);
+        if a_len > 0 && b_len > 0 {
+            self.is_minimal = false;
+        }
+        for a_item in a.take(a_len) {
+            diff.push_first(a_item);
+        }
+        for b_item in b.clone().take(b_len) {
+            diff.push_second(b_item);
+        }
+        for b_item in b.skip(b_len) {
+            diff.push_both(b_item);
+        }
+        Ok(())
+    }
+}
+
+/// The edit script of a `Difference`, which pushes a replacement
+/// as a deletion and an insertion
+struct Unreplaced<SeqC>(SeqC);
+
+impl<T, SeqC: Difference<T>> Difference<T> for Unreplaced<SeqC> {
+    fn empty() -> Self {
+        Self(SeqC::empty())
+    }
+
+    fn push_first(&mut self, item: T) {
+        self.0.push_first(item)
+    }
+
+    fn push_both(&mut self, item: T) {
+        self.0.push_both(item)
+    }
+
+    fn push_second(&mut self, item: T) {
+        self.0.push_second(item)
+    }
+}
+
+impl<T, SeqC: Difference<T>> EditScript<T> for Unreplaced<SeqC> {
+    fn push_replace(&mut self, first: T, second: T) {
+        self.0.push_first(first);
+        self.0.push_second(second);
+    }
+}
+
+/// The scoring of the Hirschberg's recursion, which splits the sequences by
+/// the last lines of its score matrices
+pub(crate) trait SplitScoring<T>: Scoring<T> {
+    type Line;
+
+    fn last_line<IterA, IterB, L>(&self, a: IterA, b: IterB, limit: &L) -> Result<Self::Line, Interrupted>
+    where
+        IterA: ExactSizeIterator<Item = T>,
+        IterB: ExactSizeIterator<Item = T> + Clone,
+        L: Limit + ?Sized;
+
+    fn score(line: &Self::Line, j: usize) -> i64;
+}
+
+/// The scoring of the longest common subsequence, whose score lines are
+/// the `Line`s of `score_last_line`
+struct LcsScoring<Line>(PhantomData<fn() -> Line>);
+
+impl<T: PartialEq, Line> Scoring<T> for LcsScoring<Line> {
+    #[inline]
+    fn pair(&self, a: &T, b: &T) -> i64 {
+        SimpleScoring::LCS.pair(a, b)
+    }
+
+    #[inline]
+    fn gap(&self) -> i64 {
+        Scoring::<T>::gap(&SimpleScoring::LCS)
+    }
+}
+
+impl<T: PartialEq, Line: NwScoreLine> SplitScoring<T> for LcsScoring<Line> {
+    type Line = Line;
+
+    fn last_line<IterA, IterB, L>(&self, a: IterA, b: IterB, limit: &L) -> Result<Line, Interrupted>
+    where
+        IterA: ExactSizeIterator<Item = T>,
+        IterB: ExactSizeIterator<Item = T> + Clone,
+        L: Limit + ?Sized,
+    {
+        try_score_last_line(a, b, limit)
+    }
+
+    #[inline]
+    fn score(line: &Line, j: usize) -> i64 {
+        line[j] as i64
+    }
+}
+
+/// The Hirschberg's recursion of the optimal global alignment with the `scoring`,
+/// which the LCS diffs and the `needleman_wunsch` share. The aligned pairs of
+/// equal items are pushed as both, and the rest of them as replaced.
+pub(crate) fn limited_hirschberg_diff<SeqA, SeqB, SeqC, S, L>(
+    a: SeqA, b: SeqB, scoring: &S, full_matrix_cells: usize, limit: &L, degrade: bool,
+) -> Result<Bounded<SeqC>, Interrupted>
+where
+    SeqA: IntoIterator,
+    SeqA::Item: PartialEq,
+    SeqB: IntoIterator<Item = SeqA::Item>,
+    SeqA::IntoIter: SequenceIterator,
+    SeqB::IntoIter: SequenceIterator,
+    SeqC: EditScript<SeqA::Item>,
+    S: SplitScoring<SeqA::Item> + ?Sized,
+    L: Limit + ?Sized,
+{
+    fn hirschberg_diff_inner<IterA, IterB, SeqC, S, L>(
+        mut a: SeqIter<IterA>, b: SeqIter<IterB>, diff: &mut SeqC, scoring: &S, ctx: &mut Context<'_, L>,
+    ) -> Result<(), Interrupted>
+    where
+        IterA: SequenceIterator,
+        IterA::Item: PartialEq,
+        IterB: SequenceIterator + Iterator<Item = IterA::Item>,
+        SeqC: EditScript<IterA::Item>,
+        S: SplitScoring<IterA::Item> + ?Sized,
+        L: Limit + ?Sized,
     {
         let (a_len, b_len) = (a.len(), b.len());
 
@@ -75,49 +373,183 @@ where
         } else if a_len > 0 && b_len > 0 {
             if a_len == 1 {
                 let a_item = a.next().unwrap();
-                let mut found = false;
-                for b_item in b {
-                    if b_item == a_item && !found {
-                        diff.push_both(b_item);
-                        found = true;
-                    } else {
-                        diff.push_second(b_item);
+
+                // Aligning the item against a gap is the same as aligning it against
+                // an item with the score of two gaps
+                let mut max = 2 * scoring.gap();
+                let mut k = None;
+                for (j, b_item) in b.clone().enumerate() {
+                    let score = scoring.pair(&a_item, &b_item);
+                    if score > max {
+                        max = score;
+                        k = Some(j);
                     }
                 }
-                if !found {
-                    diff.push_first(a_item);
+
+                let mut b = b.enumerate();
+                match k {
+                    Some(k) => {
+                        for (_, b_item) in b.by_ref().take(k) {
+                            diff.push_second(b_item);
+                        }
+                        let (_, b_item) = b.next().unwrap();
+                        if a_item == b_item {
+                            diff.push_both(b_item);
+                        } else {
+                            diff.push_replace(a_item, b_item);
+                        }
+                        for (_, b_item) in b {
+                            diff.push_second(b_item);
+                        }
+                    }
+                    None => {
+                        for (_, b_item) in b {
+                            diff.push_second(b_item);
+                        }
+                        diff.push_first(a_item);
+                    }
+                }
+            } else if a_len.saturating_mul(b_len) < ctx.full_matrix_cells {
+                let memory = (a_len + 1) * (b_len + 1) * mem::size_of::<i64>();
+                if !ctx.limit.admit(a_len * b_len, memory) {
+                    return ctx.interrupt(a, b, diff);
                 }
+                full_matrix_diff(a, b, diff, scoring);
+            } else if !ctx
+                .limit
+                .admit(a_len.saturating_mul(b_len), 2 * (b_len + 1) * mem::size_of::<i64>())
+            {
+                return ctx.interrupt(a, b, diff);
             } else {
                 let mid = a_len / 2;
-                let score_left: Line = score_last_line(a.clone().take(mid), b.clone());
-                let score_right: Line = score_last_line(a.clone().skip(mid).rev(), b.clone().rev());
+                let scores = scoring
+                    .last_line(a.clone().take(mid), b.clone(), ctx.limit)
+                    .and_then(|left| {
+                        scoring
+                            .last_line(a.clone().skip(mid).rev(), b.clone().rev(), ctx.limit)
+                            .map(|right| (left, right))
+                    });
+                let (score_left, score_right) = match scores {
+                    Ok(scores) => scores,
+                    Err(Interrupted) => return ctx.interrupt(a, b, diff),
+                };
 
                 let mut k = 0;
-                let mut max = 0;
-                for j in 0..score_left.len() {
-                    let m = score_left[j] + score_right[score_right.len() - 1 - j];
+                let mut max = i64::MIN;
+                for j in 0..=b_len {
+                    let m = S::score(&score_left, j) + S::score(&score_right, b_len - j);
                     if m > max {
                         max = m;
                         k = j;
                     }
                 }
 
-                hirschberg_diff_inner::<Line, _, _, _>(a.clone().take(mid), b.clone().take(k), diff);
-                hirschberg_diff_inner::<Line, _, _, _>(a.skip(mid), b.skip(k), diff);
+                hirschberg_diff_inner(a.clone().take(mid), b.clone().take(k), diff, scoring, ctx)?;
+                hirschberg_diff_inner(a.skip(mid), b.skip(k), diff, scoring, ctx)?;
             }
         }
+        Ok(())
     }
 
     let (a, b) = (a.into_iter(), b.into_iter());
     let (a_len, b_len) = (a.len(), b.len());
-    let mut lcs = SeqC::empty();
-    hirschberg_diff_inner::<Line, _, _, _>(SeqIter::new(a, a_len), SeqIter::new(b, b_len), &mut lcs);
-    lcs
+    let mut diff = SeqC::empty();
+    let mut ctx = Context {
+        full_matrix_cells,
+        limit,
+        degrade,
+        is_minimal: true,
+    };
+    hirschberg_diff_inner(
+        SeqIter::new(a, a_len),
+        SeqIter::new(b, b_len),
+        &mut diff,
+        scoring,
+        &mut ctx,
+    )?;
+    Ok(Bounded {
+        value: diff,
+        is_minimal: ctx.is_minimal,
+    })
+}
+
+/// Computes the diff by a traceback over the full score matrix,
+/// which takes O(mn) time and O(mn) space. The pairs of equal items are
+/// preferred, and the mismatched ones only when they score above the gaps.
+fn full_matrix_diff<IterA, IterB, SeqC, S>(a: IterA, b: IterB, diff: &mut SeqC, scoring: &S)
+where
+    IterA: Iterator,
+    IterA::Item: PartialEq,
+    IterB: Iterator<Item = IterA::Item>,
+    SeqC: EditScript<IterA::Item>,
+    S: Scoring<IterA::Item> + ?Sized,
+{
+    enum Step {
+        First,
+        Both,
+        Replace,
+        Second,
+    }
+
+    let (a, b): (Vec<_>, Vec<_>) = (a.collect(), b.collect());
+    let gap = scoring.gap();
+    let width = b.len() + 1;
+    let mut matrix = vec![0; (a.len() + 1) * width];
+    for (j, cell) in matrix[..width].iter_mut().enumerate() {
+        *cell = j as i64 * gap;
+    }
+    for (i, a_item) in a.iter().enumerate() {
+        matrix[(i + 1) * width] = (i + 1) as i64 * gap;
+        for (j, b_item) in b.iter().enumerate() {
+            matrix[(i + 1) * width + j + 1] = (matrix[i * width + j] + scoring.pair(a_item, b_item))
+                .max(matrix[i * width + j + 1] + gap)
+                .max(matrix[(i + 1) * width + j] + gap);
+        }
+    }
+
+    let mut steps = Vec::with_capacity(a.len() + b.len());
+    let (mut i, mut j) = (a.len(), b.len());
+    while i > 0 || j > 0 {
+        let cell = matrix[i * width + j];
+        let diagonal = i > 0 && j > 0 && cell == matrix[(i - 1) * width + j - 1] + scoring.pair(&a[i - 1], &b[j - 1]);
+        if diagonal && a[i - 1] == b[j - 1] {
+            steps.push(Step::Both);
+            i -= 1;
+            j -= 1;
+        } else if diagonal && cell > matrix[i * width + j - 1] + gap && cell > matrix[(i - 1) * width + j] + gap {
+            steps.push(Step::Replace);
+            i -= 1;
+            j -= 1;
+        } else if j > 0 && (i == 0 || matrix[i * width + j - 1] >= matrix[(i - 1) * width + j]) {
+            steps.push(Step::Second);
+            j -= 1;
+        } else {
+            steps.push(Step::First);
+            i -= 1;
+        }
+    }
+
+    let (mut a, mut b) = (a.into_iter(), b.into_iter());
+    for step in steps.into_iter().rev() {
+        match step {
+            Step::First => diff.push_first(a.next().unwrap()),
+            Step::Both => {
+                a.next();
+                diff.push_both(b.next().unwrap());
+            }
+            Step::Replace => diff.push_replace(a.next().unwrap(), b.next().unwrap()),
+            Step::Second => diff.push_second(b.next().unwrap()),
+        }
+    }
 }
 
 pub trait HirschbergAlg {
     type Line: NwScoreLine;
 
+    /// Sub-problems with less cells than this are solved by the full score
+    /// matrix traceback, zero disables it
+    const FULL_MATRIX_CELLS: usize = 0;
+
     #[inline]
     fn lcs<SeqA, SeqB, SeqC>(a: SeqA, b: SeqB) -> SeqC
     where
@@ -128,7 +560,7 @@ pub trait HirschbergAlg {
         SeqB::IntoIter: SequenceIterator,
         SeqC: Insert<SeqA::Item>,
     {
-        hirschberg_diff::<Self::Line, _, _, Lcs<SeqC>>(a, b).0
+        hybrid_hirschberg_diff::<Self::Line, _, _, Lcs<SeqC>>(a, b, Self::FULL_MATRIX_CELLS).0
     }
 
     #[inline]
@@ -141,7 +573,99 @@ pub trait HirschbergAlg {
         SeqB::IntoIter: SequenceIterator,
         SeqC: Insert<DiffItem<SeqA::Item>>,
     {
-        hirschberg_diff::<Self::Line, _, _, Diff<SeqC>>(a, b).0
+        hybrid_hirschberg_diff::<Self::Line, _, _, Diff<SeqC>>(a, b, Self::FULL_MATRIX_CELLS).0
+    }
+
+    /// Returns the edit script with the substitutions of the items, which
+    /// is the minimal by the Levenshtein distance rather than by the LCS
+    #[inline]
+    fn edits<SeqA, SeqB, SeqC>(a: SeqA, b: SeqB) -> SeqC
+    where
+        SeqA: IntoIterator,
+        SeqA::Item: PartialEq,
+        SeqB: IntoIterator<Item = SeqA::Item>,
+        SeqA::IntoIter: SequenceIterator,
+        SeqB::IntoIter: SequenceIterator,
+        SeqC: Insert<EditItem<SeqA::Item>>,
+    {
+        levenshtein_diff::<_, _, Edits<SeqC>>(a, b).0
+    }
+
+    /// Returns the optimal global alignment with the `scoring`
+    #[inline]
+    fn align<SeqA, SeqB, SeqC, S>(a: SeqA, b: SeqB, scoring: &S) -> SeqC
+    where
+        SeqA: IntoIterator,
+        SeqA::Item: PartialEq,
+        SeqB: IntoIterator<Item = SeqA::Item>,
+        SeqA::IntoIter: SequenceIterator,
+        SeqB::IntoIter: SequenceIterator,
+        SeqC: Insert<EditItem<SeqA::Item>>,
+        S: Scoring<SeqA::Item> + ?Sized,
+    {
+        needleman_wunsch::<_, _, Edits<SeqC>, _>(a, b, scoring).0
+    }
+
+    /// Returns the diff of the optimal global alignment with the affine gap
+    /// scores of the `scoring`, which keeps the changes in contiguous blocks
+    #[inline]
+    fn affine_diff<SeqA, SeqB, SeqC, S>(a: SeqA, b: SeqB, scoring: &S) -> SeqC
+    where
+        SeqA: IntoIterator,
+        SeqA::Item: PartialEq,
+        SeqB: IntoIterator<Item = SeqA::Item>,
+        SeqA::IntoIter: SequenceIterator,
+        SeqB::IntoIter: SequenceIterator,
+        SeqC: Insert<DiffItem<SeqA::Item>>,
+        S: Scoring<SeqA::Item> + ?Sized,
+    {
+        gotoh::<_, _, Diff<SeqC>, _>(a, b, scoring).0
+    }
+
+    #[inline]
+    fn try_lcs<SeqA, SeqB, SeqC, L>(a: SeqA, b: SeqB, limit: &L) -> Result<SeqC, Interrupted>
+    where
+        SeqA: IntoIterator,
+        SeqA::Item: PartialEq,
+        SeqB: IntoIterator<Item = SeqA::Item>,
+        SeqA::IntoIter: SequenceIterator,
+        SeqB::IntoIter: SequenceIterator,
+        SeqC: Insert<SeqA::Item>,
+        L: Limit + ?Sized,
+    {
+        try_hirschberg_diff::<Self::Line, _, _, Lcs<SeqC>, _>(a, b, Self::FULL_MATRIX_CELLS, limit).map(|lcs| lcs.0)
+    }
+
+    #[inline]
+    fn try_diff<SeqA, SeqB, SeqC, L>(a: SeqA, b: SeqB, limit: &L) -> Result<SeqC, Interrupted>
+    where
+        SeqA: IntoIterator,
+        SeqA::Item: PartialEq,
+        SeqB: IntoIterator<Item = SeqA::Item>,
+        SeqA::IntoIter: SequenceIterator,
+        SeqB::IntoIter: SequenceIterator,
+        SeqC: Insert<DiffItem<SeqA::Item>>,
+        L: Limit + ?Sized,
+    {
+        try_hirschberg_diff::<Self::Line, _, _, Diff<SeqC>, _>(a, b, Self::FULL_MATRIX_CELLS, limit).map(|diff| diff.0)
+    }
+
+    #[inline]
+    fn bounded_diff<SeqA, SeqB, SeqC, L>(a: SeqA, b: SeqB, limit: &L) -> Bounded<SeqC>
+    where
+        SeqA: IntoIterator,
+        SeqA::Item: PartialEq,
+        SeqB: IntoIterator<Item = SeqA::Item>,
+        SeqA::IntoIter: SequenceIterator,
+        SeqB::IntoIter: SequenceIterator,
+        SeqC: Insert<DiffItem<SeqA::Item>>,
+        L: Limit + ?Sized,
+    {
+        let diff = bounded_hirschberg_diff::<Self::Line, _, _, Diff<SeqC>, _>(a, b, Self::FULL_MATRIX_CELLS, limit);
+        Bounded {
+            value: diff.value.0,
+            is_minimal: diff.is_minimal,
+        }
     }
 }
 
@@ -151,6 +675,16 @@ impl HirschbergAlg for Hirschberg {
     type Line = Vec<usize>;
 }
 
+/// The Hirschberg's algorithm that falls back to the full score matrix
+/// for the sub-problems below `DEFAULT_FULL_MATRIX_CELLS`
+pub struct HybridHirschberg;
+
+impl HirschbergAlg for HybridHirschberg {
+    type Line = Vec<usize>;
+
+    const FULL_MATRIX_CELLS: usize = DEFAULT_FULL_MATRIX_CELLS;
+}
+
 #[cfg(test)]
 mod tests {
     use std::fmt::Display;
@@ -233,14 +767,153 @@ mod tests {
 
         let diff: Vec<_> = Hirschberg::diff("abcdfghjqvz".chars_iter(), "abcdefgijkrxyz".chars_iter());
         assert_eq!(format_diff(diff), "abcd+efg-h+ij-q-v+k+r+x+yz".to_string());
+
+        let (a, b) = (CharBuf::new("abcdfghjqvz"), CharBuf::new("abcdefgijkrxyz"));
+        let diff: Vec<_> = Hirschberg::diff(&a, &b);
+        assert_eq!(format_diff(diff), "abcd+efg-h+ij-q-v+k+r+x+yz".to_string());
+
+        let (a, b) = (vec!["a", "b", "c", "d"], vec!["b", "d", "e"]);
+        let diff: Vec<_> = Hirschberg::diff((&a).index_iter(), (&b).index_iter());
+        assert_eq!(format_diff(diff), "-ab-cd+e".to_string());
+    }
+
+    #[test]
+    fn test_hybrid_hirschberg_diff() {
+        let diff: Vec<_> = HybridHirschberg::diff(b"", b"");
+        assert_eq!(diff, Vec::<DiffItem<&u8>>::new());
+
+        let diff: Vec<_> = HybridHirschberg::diff("AGTACGCA".chars_iter(), "TATGC".chars_iter());
+        assert_eq!(format_diff(diff), "-A-GTA-C+TGC-A".to_string());
+
+        let diff: Vec<_> = HybridHirschberg::diff("ABCBDAB".chars_iter(), "BDCABA".chars_iter());
+        assert_eq!(format_diff(diff), "-A-B-CBD+CAB+A".to_string());
+
+        let diff: Vec<_> = HybridHirschberg::diff("abcdfghjqvz".chars_iter(), "abcdefgijkrxyz".chars_iter());
+        assert_eq!(format_diff(diff), "abcd+efg-h+ij-q-v+k+r+x+yz".to_string());
+
+        let (a, b) = ("ACCGGTCGAGTGCGCGGAAGCCGGCCGAA", "GTCGTTCGGAATGCCGTTGCTCTGTAAA");
+        let lcs: String = Hirschberg::lcs(a.chars_iter(), b.chars_iter());
+        for cells in &[2, 16, 64, 1024] {
+            let Lcs(hybrid_lcs): Lcs<String> =
+                hybrid_hirschberg_diff::<Vec<usize>, _, _, _>(a.chars_iter(), b.chars_iter(), *cells);
+            assert_eq!(hybrid_lcs.len(), lcs.len());
+        }
+    }
+
+    #[test]
+    fn test_limited_hirschberg_diff() {
+        use std::{
+            sync::atomic::AtomicBool,
+            time::{Duration, Instant},
+        };
+
+        let (a, b) = ("abcdfghjqvz".chars_iter(), "abcdefgijkrxyz".chars_iter());
+        let future = Instant::now() + Duration::from_secs(3600);
+
+        let diff: Result<Vec<_>, _> = Hirschberg::try_diff(a.clone(), b.clone(), &future);
+        assert_eq!(format_diff(diff.unwrap()), "abcd+efg-h+ij-q-v+k+r+x+yz".to_string());
+
+        let diff: Bounded<Vec<_>> = Hirschberg::bounded_diff(a.clone(), b.clone(), &future);
+        assert!(diff.is_minimal);
+        assert_eq!(format_diff(diff.value), "abcd+efg-h+ij-q-v+k+r+x+yz".to_string());
+
+        let cancel = AtomicBool::new(true);
+        let diff: Result<Vec<DiffItem<char>>, _> = Hirschberg::try_diff(a.clone(), b.clone(), &cancel);
+        assert_eq!(diff, Err(Interrupted));
+
+        let lcs: Result<String, _> = Hirschberg::try_lcs(a.clone(), b.clone(), &cancel);
+        assert_eq!(lcs, Err(Interrupted));
+
+        let diff: Bounded<Vec<_>> = Hirschberg::bounded_diff(a.clone(), b.clone(), &cancel);
+        assert!(!diff.is_minimal);
+        assert_eq!(
+            format_diff(diff.value),
+            "abcd-f-g-h-j-q-v+e+f+g+i+j+k+r+x+yz".to_string()
+        );
+
+        let diff: Bounded<Vec<_>> = HybridHirschberg::bounded_diff(a.clone(), b.clone(), &cancel);
+        assert!(!diff.is_minimal);
+        assert_eq!(
+            format_diff(diff.value),
+            "abcd-f-g-h-j-q-v+e+f+g+i+j+k+r+x+yz".to_string()
+        );
+
+        // The trimming alone is enough to get the minimal diff of an insertion
+        let diff: Bounded<Vec<_>> = Hirschberg::bounded_diff("abcz".chars_iter(), "abxyz".chars_iter(), &cancel);
+        assert!(!diff.is_minimal);
+        let diff: Bounded<Vec<_>> = Hirschberg::bounded_diff("abz".chars_iter(), "abxyz".chars_iter(), &cancel);
+        assert!(diff.is_minimal);
+        assert_eq!(format_diff(diff.value), "ab+x+yz".to_string());
+    }
+
+    #[test]
+    fn test_budget_hirschberg_diff() {
+        let (a, b) = ("abcdfghjqvz".chars_iter(), "abcdefgijkrxyz".chars_iter());
+
+        let diff: Bounded<Vec<_>> = Hirschberg::bounded_diff(a.clone(), b.clone(), &Budget::new(1000, 1024));
+        assert!(diff.is_minimal);
+        assert_eq!(format_diff(diff.value), "abcd+efg-h+ij-q-v+k+r+x+yz".to_string());
+
+        let diff: Bounded<Vec<_>> = Hirschberg::bounded_diff(a.clone(), b.clone(), &Budget::new(0, 1024));
+        assert!(!diff.is_minimal);
+        assert_eq!(
+            format_diff(diff.value),
+            "abcd-f-g-h-j-q-v+e+f+g+i+j+k+r+x+yz".to_string()
+        );
+
+        let diff: Bounded<Vec<_>> = Hirschberg::bounded_diff(a.clone(), b.clone(), &Budget::new(1000, 8));
+        assert!(!diff.is_minimal);
+
+        let diff: Result<Vec<DiffItem<char>>, _> =
+            HybridHirschberg::try_diff(a.clone(), b.clone(), &Budget::new(100, 0));
+        assert_eq!(diff, Err(Interrupted));
+
+        // The rest of the budget is still spent on the smaller sub-problems
+        let budget = Budget::new(200, 1024);
+        let diff: Bounded<Vec<_>> = Hirschberg::bounded_diff(a, b, &budget);
+        assert!(!diff.is_minimal);
+        assert!(budget.remaining_cells() < 200 - 11 * 14);
+    }
+
+    #[test]
+    fn test_hirschberg_edits() {
+        let edits: Vec<_> = Hirschberg::edits(vec!["host", "port = 80", "user"], vec!["host", "port = 8080", "user"]);
+        assert_eq!(
+            edits,
+            vec![
+                EditItem::Both("host"),
+                EditItem::Replace("port = 80", "port = 8080"),
+                EditItem::Both("user")
+            ]
+        );
+        assert_eq!(edits[1].to_string(), "~ port = 80 -> port = 8080");
+
+        let alignment: Vec<_> = Hirschberg::align(b"GCATGCU", b"GATTACA", &SimpleScoring::new(1, -1, -1));
+        assert_eq!(
+            alignment,
+            vec![
+                EditItem::Both(&b'G'),
+                EditItem::First(&b'C'),
+                EditItem::Both(&b'A'),
+                EditItem::Both(&b'T'),
+                EditItem::Replace(&b'G', &b'T'),
+                EditItem::Second(&b'A'),
+                EditItem::Both(&b'C'),
+                EditItem::Replace(&b'U', &b'A'),
+            ]
+        );
+
+        let scoring = Affine::new(SimpleScoring::new(1, -1, 0), -2);
+        let diff: Vec<_> = Hirschberg::affine_diff("abcdfghjqvz".chars_iter(), "abcdefgijkrxyz".chars_iter(), &scoring);
+        assert_eq!(format_diff(diff), "abcd-f-g-h-j-q-v+e+f+g+i+j+k+r+x+yz".to_string());
     }
 
     fn format_diff(diff: Vec<DiffItem<impl Display>>) -> String {
         let mut line = String::new();
         for item in diff {
             match &item {
-                DiffItem::First(_) => line.push_str("-"),
-                DiffItem::Second(_) => line.push_str("+"),
+                DiffItem::First(_) => line.push('-'),
+                DiffItem::Second(_) => line.push('+'),
                 _ => (),
             }
             line.push_str(&item.into_inner().to_string());
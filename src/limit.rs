@@ -0,0 +1,234 @@
+use std::{
+    cell::Cell,
+    error::Error,
+    fmt,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Instant,
+};
+
+/// The interface of a condition that stops a long-running diff. It is checked
+/// before every sub-problem of the recursion and periodically while computing
+/// the score lines.
+pub trait Limit {
+    fn is_exceeded(&self) -> bool;
+
+    /// Returns whether `admit` would let the sub-problem be solved, without
+    /// accounting for it
+    fn allows(&self, cells: usize, memory: usize) -> bool {
+        let _ = (cells, memory);
+        !self.is_exceeded()
+    }
+
+    /// Accounts for a sub-problem that is about to take `cells` of the score
+    /// matrix and `memory` bytes, returns `false` if it may not be solved
+    fn admit(&self, cells: usize, memory: usize) -> bool {
+        self.allows(cells, memory)
+    }
+}
+
+/// The limit that is never exceeded
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoLimit;
+
+impl Limit for NoLimit {
+    #[inline]
+    fn is_exceeded(&self) -> bool {
+        false
+    }
+}
+
+/// A deadline, exceeded once the current time reaches it
+impl Limit for Instant {
+    #[inline]
+    fn is_exceeded(&self) -> bool {
+        Instant::now() >= *self
+    }
+}
+
+/// A cancellation token, exceeded once it is set to `true`
+impl Limit for AtomicBool {
+    #[inline]
+    fn is_exceeded(&self) -> bool {
+        self.load(Ordering::Relaxed)
+    }
+}
+
+impl<L: Limit> Limit for Option<L> {
+    #[inline]
+    fn is_exceeded(&self) -> bool {
+        self.as_ref().is_some_and(Limit::is_exceeded)
+    }
+
+    #[inline]
+    fn allows(&self, cells: usize, memory: usize) -> bool {
+        self.as_ref().map_or(true, |limit| limit.allows(cells, memory))
+    }
+
+    #[inline]
+    fn admit(&self, cells: usize, memory: usize) -> bool {
+        self.as_ref().map_or(true, |limit| limit.admit(cells, memory))
+    }
+}
+
+impl<L: Limit + ?Sized> Limit for &L {
+    #[inline]
+    fn is_exceeded(&self) -> bool {
+        (**self).is_exceeded()
+    }
+
+    #[inline]
+    fn allows(&self, cells: usize, memory: usize) -> bool {
+        (**self).allows(cells, memory)
+    }
+
+    #[inline]
+    fn admit(&self, cells: usize, memory: usize) -> bool {
+        (**self).admit(cells, memory)
+    }
+}
+
+/// Both limits at once, exceeded when any of them is exceeded. A sub-problem
+/// is accounted for by neither of them unless both admit it.
+impl<A: Limit, B: Limit> Limit for (A, B) {
+    #[inline]
+    fn is_exceeded(&self) -> bool {
+        self.0.is_exceeded() || self.1.is_exceeded()
+    }
+
+    #[inline]
+    fn allows(&self, cells: usize, memory: usize) -> bool {
+        self.0.allows(cells, memory) && self.1.allows(cells, memory)
+    }
+
+    #[inline]
+    fn admit(&self, cells: usize, memory: usize) -> bool {
+        self.allows(cells, memory) && self.0.admit(cells, memory) && self.1.admit(cells, memory)
+    }
+}
+
+/// The cost and memory budget of a diff, which guarantees the bounded time
+/// for any input. Every sub-problem is charged up front: the ones that cost
+/// more score matrix cells than remain in the budget, or need more memory than
+/// allowed at once, are not solved exactly.
+#[derive(Clone, Debug)]
+pub struct Budget {
+    cells: Cell<usize>,
+    max_memory: usize,
+}
+
+impl Budget {
+    pub fn new(max_cells: usize, max_memory: usize) -> Self {
+        Self {
+            cells: Cell::new(max_cells),
+            max_memory,
+        }
+    }
+
+    pub fn remaining_cells(&self) -> usize {
+        self.cells.get()
+    }
+}
+
+impl Limit for Budget {
+    /// The budget is charged before a sub-problem is started,
+    /// so it never stops a computation in progress
+    #[inline]
+    fn is_exceeded(&self) -> bool {
+        false
+    }
+
+    fn allows(&self, cells: usize, memory: usize) -> bool {
+        cells <= self.cells.get() && memory <= self.max_memory
+    }
+
+    fn admit(&self, cells: usize, memory: usize) -> bool {
+        if self.allows(cells, memory) {
+            self.cells.set(self.cells.get() - cells);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// The error of a diff stopped by its `Limit`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Interrupted;
+
+impl fmt::Display for Interrupted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the diff was interrupted by its limit")
+    }
+}
+
+impl Error for Interrupted {}
+
+/// The result of a diff that degrades instead of failing when its `Limit`
+/// is exceeded. The regions that were not refined are only trimmed of their
+/// common prefix and suffix, and the rest is reported as deleted from the first
+/// sequence and inserted into the second one, so the diff is still valid,
+/// but may be not minimal.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Bounded<T> {
+    pub value: T,
+    pub is_minimal: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_limits() {
+        assert!(!NoLimit.is_exceeded());
+
+        let past = Instant::now();
+        let future = past + Duration::from_secs(3600);
+        assert!(past.is_exceeded());
+        assert!(!future.is_exceeded());
+
+        let cancel = AtomicBool::new(false);
+        assert!(!cancel.is_exceeded());
+        assert!(!(&cancel, future).is_exceeded());
+        cancel.store(true, Ordering::Relaxed);
+        assert!(cancel.is_exceeded());
+        assert!((&cancel, future).is_exceeded());
+
+        assert!(!None::<Instant>.is_exceeded());
+        assert!(Some(past).is_exceeded());
+    }
+
+    #[test]
+    fn test_budget() {
+        let budget = Budget::new(100, 64);
+        assert!(!budget.is_exceeded());
+        assert!(budget.admit(60, 64));
+        assert_eq!(budget.remaining_cells(), 40);
+        assert!(!budget.admit(50, 8));
+        assert!(!budget.admit(10, 128));
+        assert!(budget.admit(40, 8));
+        assert_eq!(budget.remaining_cells(), 0);
+        assert!(!budget.admit(1, 8));
+        assert!(budget.admit(0, 0));
+    }
+
+    #[test]
+    fn test_budgets_pair() {
+        let budgets = (Budget::new(100, 64), Budget::new(50, 64));
+        assert!(budgets.admit(30, 8));
+        assert_eq!((budgets.0.remaining_cells(), budgets.1.remaining_cells()), (70, 20));
+        assert!(!budgets.admit(40, 8));
+        assert_eq!((budgets.0.remaining_cells(), budgets.1.remaining_cells()), (70, 20));
+        assert!(!budgets.admit(10, 128));
+        assert_eq!((budgets.0.remaining_cells(), budgets.1.remaining_cells()), (70, 20));
+        assert!(budgets.admit(20, 8));
+        assert_eq!((budgets.0.remaining_cells(), budgets.1.remaining_cells()), (50, 0));
+
+        let nested = (Budget::new(100, 64), (NoLimit, Budget::new(10, 64)));
+        assert!(!nested.admit(20, 8));
+        assert_eq!(nested.0.remaining_cells(), 100);
+    }
+}
@@ -0,0 +1,119 @@
+use std::ops::Range;
+
+use crate::{align::score_rows, needleman_wunsch, EditScript, NoLimit, Scoring, SeqIter, SequenceIterator};
+
+/// The best matching regions of two sequences
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LocalAlignment<T> {
+    /// The range of the region in the first sequence
+    #[cfg_attr(feature = "serde", serde(rename = "old", with = "crate::ops::range"))]
+    pub first: Range<usize>,
+    /// The range of the region in the second sequence
+    #[cfg_attr(feature = "serde", serde(rename = "new", with = "crate::ops::range"))]
+    pub second: Range<usize>,
+    pub score: i64,
+    /// The global alignment of the regions
+    pub alignment: T,
+}
+
+/// Finds the best local alignment of `a` and `b` with the linear gap scores
+/// of the `scoring` by the Smith-Waterman algorithm in O(mn) time and O(m + n)
+/// space. The first pass finds where the best region ends, the second pass over
+/// the reversed prefixes finds where it starts, and then the regions are
+/// aligned globally. The alignment is empty if no pair of items scores above zero.
+pub fn smith_waterman<SeqA, SeqB, SeqC, S>(a: SeqA, b: SeqB, scoring: &S) -> LocalAlignment<SeqC>
+where
+    SeqA: IntoIterator,
+    SeqA::Item: PartialEq,
+    SeqB: IntoIterator<Item = SeqA::Item>,
+    SeqA::IntoIter: SequenceIterator,
+    SeqB::IntoIter: SequenceIterator,
+    SeqC: EditScript<SeqA::Item>,
+    S: Scoring<SeqA::Item> + ?Sized,
+{
+    let (a, b) = (a.into_iter(), b.into_iter());
+    let (a_len, b_len) = (a.len(), b.len());
+    let (a, b) = (SeqIter::new(a, a_len), SeqIter::new(b, b_len));
+
+    let mut score = 0;
+    let mut end = (0, 0);
+    let _ = score_rows(a.clone(), b.clone(), scoring, true, &NoLimit, |i, j, cell| {
+        if cell > score {
+            score = cell;
+            end = (i, j);
+        }
+    });
+
+    let mut start = end;
+    if score > 0 {
+        let (a_prefix, b_prefix) = (a.clone().take(end.0).rev(), b.clone().take(end.1).rev());
+        let mut found = false;
+        let _ = score_rows(a_prefix, b_prefix, scoring, false, &NoLimit, |i, j, cell| {
+            if cell == score && !found {
+                found = true;
+                start = (end.0 - i, end.1 - j);
+            }
+        });
+    }
+
+    let (first, second) = (start.0..end.0, start.1..end.1);
+    let alignment = needleman_wunsch(
+        a.skip(first.start).take(first.len()),
+        b.skip(second.start).take(second.len()),
+        scoring,
+    );
+    LocalAlignment {
+        first,
+        second,
+        score,
+        alignment,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AsCharsIter, EditItem, Edits, SimpleScoring};
+
+    #[test]
+    fn test_smith_waterman() {
+        let scoring = SimpleScoring::new(3, -3, -2);
+
+        let local: LocalAlignment<Edits<Vec<_>>> =
+            smith_waterman("TGTTACGG".chars_iter(), "GGTTGACTA".chars_iter(), &scoring);
+        assert_eq!(local.first, 1..6);
+        assert_eq!(local.second, 1..7);
+        assert_eq!(local.score, 13);
+        assert_eq!(
+            local.alignment.0,
+            vec![
+                EditItem::Both('G'),
+                EditItem::Both('T'),
+                EditItem::Both('T'),
+                EditItem::Second('G'),
+                EditItem::Both('A'),
+                EditItem::Both('C'),
+            ]
+        );
+
+        let local: LocalAlignment<Edits<Vec<_>>> = smith_waterman("abc".chars_iter(), "xyz".chars_iter(), &scoring);
+        assert_eq!(local.first, 0..0);
+        assert_eq!(local.second, 0..0);
+        assert_eq!(local.score, 0);
+        assert!(local.alignment.0.is_empty());
+
+        let local: LocalAlignment<Edits<Vec<EditItem<char>>>> =
+            smith_waterman("".chars_iter(), "".chars_iter(), &scoring);
+        assert_eq!(local.score, 0);
+
+        // A snippet found in a file that has been edited since
+        let file = "fn main() {\n    let x = compute(1, 2);\n    println!(\"{}\", x);\n}\n";
+        let snippet = "let x = compute(1, 3);";
+        let local: LocalAlignment<Edits<Vec<_>>> =
+            smith_waterman(file.chars_iter(), snippet.chars_iter(), &SimpleScoring::new(2, -3, -3));
+        assert_eq!(&file[local.first.clone()], "let x = compute(1, 2);");
+        assert_eq!(local.second, 0..snippet.len());
+        assert_eq!(local.score, 2 * 21 - 3);
+    }
+}
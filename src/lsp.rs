@@ -0,0 +1,318 @@
+//! The Language Server Protocol `TextEdit`s that turn an old text into a new one.
+//! The texts are diffed by lines, and the replaced blocks of lines are refined
+//! to chars, so the edits are small and keep the unchanged text intact.
+//! The positions refer to the old text and count the characters in the code
+//! units of the negotiated `PositionEncoding`. With the `serde` feature
+//! the types are serialized as the LSP JSON.
+//!
+//! ```
+//! use ng_diff::lsp::{text_edits, Position, PositionEncoding};
+//!
+//! let edits = text_edits("let 😀 = a;\n", "let 😀 = b;\n", PositionEncoding::Utf16);
+//! assert_eq!(edits.len(), 1);
+//! assert_eq!(edits[0].range.start, Position { line: 0, character: 9 });
+//! assert_eq!(edits[0].new_text, "b");
+//! ```
+
+use std::convert::TryFrom;
+
+use crate::{diff_ops, format, DiffOp, DiffTag, Hirschberg, HirschbergAlg};
+
+/// The largest `m * n` of the lengths in chars of the replaced blocks that
+/// are refined, the larger blocks are replaced whole
+const MAX_REFINE_CELLS: usize = 1 << 22;
+
+/// The code units that the characters of a `Position` are counted in
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PositionEncoding {
+    #[cfg_attr(feature = "serde", serde(rename = "utf-8"))]
+    Utf8,
+    /// The encoding that LSP clients support by default
+    #[cfg_attr(feature = "serde", serde(rename = "utf-16"))]
+    Utf16,
+    #[cfg_attr(feature = "serde", serde(rename = "utf-32"))]
+    Utf32,
+}
+
+impl PositionEncoding {
+    /// Returns the number of the code units of the char
+    pub fn char_len(self, c: char) -> usize {
+        match self {
+            Self::Utf8 => c.len_utf8(),
+            Self::Utf16 => c.len_utf16(),
+            Self::Utf32 => 1,
+        }
+    }
+}
+
+/// A zero-based position in a text. The lines are terminated by `\n`, `\r\n` or `\r`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Position {
+    pub line: u32,
+    pub character: u32,
+}
+
+/// The range between the positions, the end one is exclusive
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Range {
+    pub start: Position,
+    pub end: Position,
+}
+
+/// The replacement of the range of the old text by the new text
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TextEdit {
+    pub range: Range,
+    #[cfg_attr(feature = "serde", serde(rename = "newText"))]
+    pub new_text: String,
+}
+
+/// Returns the edits that turn the `old` text into the `new` one, ordered
+/// by their positions and not overlapping, so they can be applied at once
+/// as the `TextEdit[]` of LSP
+pub fn text_edits(old: &str, new: &str, encoding: PositionEncoding) -> Vec<TextEdit> {
+    let (old_lines, new_lines) = (format::lines(old), format::lines(new));
+    let diff: Vec<_> = Hirschberg::diff(old_lines.iter(), new_lines.iter());
+    let (old_offsets, new_offsets) = (offsets(&old_lines), offsets(&new_lines));
+
+    let index = LineIndex::new(old);
+    let mut edits = Vec::new();
+    let mut push_edit = |start: usize, end: usize, new_text: &str| {
+        edits.push(TextEdit {
+            range: Range {
+                start: index.position(start, encoding),
+                end: index.position(end, encoding),
+            },
+            new_text: new_text.to_string(),
+        })
+    };
+
+    for op in diff_ops(&diff) {
+        let old_range = old_offsets[op.first.start]..old_offsets[op.first.end];
+        let new_range = new_offsets[op.second.start]..new_offsets[op.second.end];
+        match op.tag {
+            DiffTag::Equal => (),
+            DiffTag::Delete | DiffTag::Insert => push_edit(old_range.start, old_range.end, &new[new_range]),
+            DiffTag::Replace => {
+                let (old_block, new_block) = (&old[old_range.clone()], &new[new_range]);
+                match refine(old_block, new_block) {
+                    Some(ops) => {
+                        let (old_tokens, new_tokens) = (token_offsets(old_block), token_offsets(new_block));
+                        for op in ops.iter().filter(|op| op.tag != DiffTag::Equal) {
+                            push_edit(
+                                old_range.start + old_tokens[op.first.start],
+                                old_range.start + old_tokens[op.first.end],
+                                &new_block[new_tokens[op.second.start]..new_tokens[op.second.end]],
+                            );
+                        }
+                    }
+                    None => push_edit(old_range.start, old_range.end, new_block),
+                }
+            }
+        }
+    }
+    edits
+}
+
+/// Diffs the blocks by chars, unless they are too large
+fn refine(old: &str, new: &str) -> Option<Vec<DiffOp>> {
+    let (old_tokens, new_tokens) = (tokens(old), tokens(new));
+    if old_tokens.len().saturating_mul(new_tokens.len()) > MAX_REFINE_CELLS {
+        return None;
+    }
+    let diff: Vec<_> = Hirschberg::diff(old_tokens.iter(), new_tokens.iter());
+    Some(diff_ops(&diff))
+}
+
+/// Splits the text into the chars, keeping the `\r\n` pairs whole so that
+/// no edit ends between them
+fn tokens(text: &str) -> Vec<&str> {
+    let offsets = token_offsets(text);
+    offsets.windows(2).map(|pair| &text[pair[0]..pair[1]]).collect()
+}
+
+/// Returns the byte offsets of the tokens of the text and of its end
+fn token_offsets(text: &str) -> Vec<usize> {
+    let mut offsets: Vec<usize> = Vec::with_capacity(text.len() + 1);
+    for (offset, c) in text.char_indices() {
+        if !(c == '\n' && offset > 0 && text.as_bytes()[offset - 1] == b'\r') {
+            offsets.push(offset);
+        }
+    }
+    offsets.push(text.len());
+    offsets
+}
+
+/// Returns the byte offsets of the lines and of the end of the text
+fn offsets(lines: &[&str]) -> Vec<usize> {
+    let mut offsets = Vec::with_capacity(lines.len() + 1);
+    offsets.push(0);
+    for line in lines {
+        offsets.push(offsets[offsets.len() - 1] + line.len());
+    }
+    offsets
+}
+
+/// The byte offsets of the starts of the lines of a text
+struct LineIndex<'a> {
+    text: &'a str,
+    starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    fn new(text: &'a str) -> Self {
+        let bytes = text.as_bytes();
+        let mut starts = vec![0];
+        for (offset, &byte) in bytes.iter().enumerate() {
+            if byte == b'\n' || (byte == b'\r' && bytes.get(offset + 1) != Some(&b'\n')) {
+                starts.push(offset + 1);
+            }
+        }
+        Self { text, starts }
+    }
+
+    fn position(&self, offset: usize, encoding: PositionEncoding) -> Position {
+        let line = self.starts.partition_point(|&start| start <= offset) - 1;
+        let character: usize = self.text[self.starts[line]..offset]
+            .chars()
+            .map(|c| encoding.char_len(c))
+            .sum();
+        Position {
+            line: u32::try_from(line).unwrap_or(u32::MAX),
+            character: u32::try_from(character).unwrap_or(u32::MAX),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Applies the edits like an LSP client, finding the offsets of the positions
+    fn apply(text: &str, edits: &[TextEdit], encoding: PositionEncoding) -> String {
+        let index = LineIndex::new(text);
+        let offset = |position: Position| {
+            let start = index.starts[position.line as usize];
+            let mut units = 0;
+            for (offset, c) in text[start..].char_indices() {
+                if units >= position.character as usize {
+                    return start + offset;
+                }
+                units += encoding.char_len(c);
+            }
+            text.len()
+        };
+
+        let mut result = String::new();
+        let mut end = 0;
+        for edit in edits {
+            let (start, next_end) = (offset(edit.range.start), offset(edit.range.end));
+            assert!(
+                end <= start && start <= next_end,
+                "the edits overlap or are out of order"
+            );
+            result += &text[end..start];
+            result += &edit.new_text;
+            end = next_end;
+        }
+        result + &text[end..]
+    }
+
+    fn edit(start: (u32, u32), end: (u32, u32), new_text: &str) -> TextEdit {
+        TextEdit {
+            range: Range {
+                start: Position {
+                    line: start.0,
+                    character: start.1,
+                },
+                end: Position {
+                    line: end.0,
+                    character: end.1,
+                },
+            },
+            new_text: new_text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_text_edits() {
+        let (old, new) = (
+            "fn main() {\n    foo(a,b);\n    baz();\n    bar();\n}\n",
+            "fn main() {\n    foo(a, b);\n    baz();\n}\n",
+        );
+        let edits = text_edits(old, new, PositionEncoding::Utf16);
+        assert_eq!(edits, vec![edit((1, 10), (1, 10), " "), edit((3, 0), (4, 0), "")]);
+        assert_eq!(apply(old, &edits, PositionEncoding::Utf16), new);
+
+        assert_eq!(
+            text_edits("a\nb", "a\nb\n", PositionEncoding::Utf8),
+            vec![edit((1, 1), (1, 1), "\n")]
+        );
+        assert_eq!(
+            text_edits("a\nb", "a\n", PositionEncoding::Utf8),
+            vec![edit((1, 0), (1, 1), "")]
+        );
+        assert_eq!(
+            text_edits("", "a\n", PositionEncoding::Utf8),
+            vec![edit((0, 0), (0, 0), "a\n")]
+        );
+        assert!(text_edits("a\nb\n", "a\nb\n", PositionEncoding::Utf8).is_empty());
+    }
+
+    #[test]
+    fn test_encodings() {
+        let (old, new) = ("é😀x\n", "é😀y\n");
+        for (encoding, character) in [
+            (PositionEncoding::Utf8, 6),
+            (PositionEncoding::Utf16, 3),
+            (PositionEncoding::Utf32, 2),
+        ] {
+            let edits = text_edits(old, new, encoding);
+            assert_eq!(edits, vec![edit((0, character), (0, character + 1), "y")]);
+            assert_eq!(apply(old, &edits, encoding), new);
+        }
+    }
+
+    #[test]
+    fn test_line_terminators() {
+        let (old, new) = ("a\r\nb\rc\n", "a\nb\r\nc\n");
+        let edits = text_edits(old, new, PositionEncoding::Utf16);
+        assert_eq!(edits, vec![edit((0, 1), (1, 0), "\n"), edit((1, 1), (2, 0), "\r\n")]);
+        assert_eq!(apply(old, &edits, PositionEncoding::Utf16), new);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let texts = [
+            "",
+            "one\ntwo\nthree\n",
+            "one\n2\nthree\nfour",
+            "zero\none\ntwo 😀\r\nthree\n",
+            "one\ntwo 😀 too\r\n\n",
+            "x",
+        ];
+        for old in texts {
+            for new in texts {
+                for encoding in [PositionEncoding::Utf8, PositionEncoding::Utf16, PositionEncoding::Utf32] {
+                    let edits = text_edits(old, new, encoding);
+                    assert_eq!(apply(old, &edits, encoding), new, "{:?} -> {:?}", old, new);
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let edits = text_edits("a\n", "b\n", PositionEncoding::Utf16);
+        assert_eq!(
+            serde_json::to_string(&edits).unwrap(),
+            r#"[{"range":{"start":{"line":0,"character":0},"end":{"line":0,"character":1}},"newText":"b"}]"#
+        );
+        assert_eq!(serde_json::to_string(&PositionEncoding::Utf16).unwrap(), r#""utf-16""#);
+    }
+}
@@ -0,0 +1,214 @@
+use std::{collections::HashMap, hash::Hash, ops::Range};
+
+use crate::{ratio, DiffItem};
+
+/// A block of the deleted items that reappears among the inserted ones
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Move {
+    /// The number of the move in the order of the destinations
+    pub id: usize,
+    /// The range of the deleted items in the first sequence
+    #[cfg_attr(feature = "serde", serde(rename = "old", with = "crate::ops::range"))]
+    pub first: Range<usize>,
+    /// The range of the inserted items in the second sequence
+    #[cfg_attr(feature = "serde", serde(rename = "new", with = "crate::ops::range"))]
+    pub second: Range<usize>,
+    /// Whether the blocks are equal, otherwise they only are similar
+    pub exact: bool,
+}
+
+/// Detects the moved blocks of at least `min_len` items, like the `--color-moved`
+/// option of git. At first the longest equal blocks of the deleted and the inserted
+/// items are paired, then the rest of the blocks are paired if the ratio `2*M/T`
+/// of their LCS length `M` to their total length `T` is at least `min_ratio`.
+pub fn detect_moves<T: Hash + Eq>(diff: &[DiffItem<T>], min_len: usize, min_ratio: f64) -> Vec<Move> {
+    let min_len = min_len.max(1);
+    let (mut a, mut b) = (Vec::new(), Vec::new());
+    let (mut deleted, mut inserted) = (Vec::new(), Vec::new());
+    for item in diff {
+        match item {
+            DiffItem::First(x) => {
+                a.push(x);
+                deleted.push(true);
+            }
+            DiffItem::Both(x) => {
+                a.push(x);
+                deleted.push(false);
+                b.push(x);
+                inserted.push(false);
+            }
+            DiffItem::Second(x) => {
+                b.push(x);
+                inserted.push(true);
+            }
+        }
+    }
+
+    let mut deleted_positions: HashMap<&T, Vec<usize>> = HashMap::new();
+    for (i, x) in a.iter().enumerate().filter(|(i, _)| deleted[*i]) {
+        deleted_positions.entry(*x).or_default().push(i);
+    }
+
+    let mut moves = Vec::new();
+    let mut j = 0;
+    while j < b.len() {
+        if !inserted[j] {
+            j += 1;
+            continue;
+        }
+
+        let mut best = (0, 0);
+        for &i in deleted_positions.get(b[j]).map(Vec::as_slice).unwrap_or_default() {
+            let len = (0..)
+                .take_while(|&k| {
+                    i + k < a.len() && j + k < b.len() && deleted[i + k] && inserted[j + k] && a[i + k] == b[j + k]
+                })
+                .count();
+            if len > best.1 {
+                best = (i, len);
+            }
+        }
+
+        let (i, len) = best;
+        if len >= min_len {
+            deleted[i..i + len]
+                .iter_mut()
+                .for_each(|is_deleted| *is_deleted = false);
+            inserted[j..j + len]
+                .iter_mut()
+                .for_each(|is_inserted| *is_inserted = false);
+            moves.push(Move {
+                id: 0,
+                first: i..i + len,
+                second: j..j + len,
+                exact: true,
+            });
+            j += len;
+        } else {
+            j += 1;
+        }
+    }
+
+    let mut deleted_blocks: Vec<_> = blocks(&deleted).filter(|block| block.len() >= min_len).collect();
+    for second in blocks(&inserted).filter(|block| block.len() >= min_len) {
+        let mut best: Option<(usize, f64)> = None;
+        for (index, first) in deleted_blocks.iter().enumerate() {
+            let ratio = ratio(a[first.clone()].iter(), b[second.clone()].iter());
+            if ratio >= min_ratio && best.map_or(true, |(_, best_ratio)| ratio > best_ratio) {
+                best = Some((index, ratio));
+            }
+        }
+
+        if let Some((index, _)) = best {
+            moves.push(Move {
+                id: 0,
+                first: deleted_blocks.remove(index),
+                second,
+                exact: false,
+            });
+        }
+    }
+
+    moves.sort_by_key(|m| m.second.start);
+    for (id, m) in moves.iter_mut().enumerate() {
+        m.id = id;
+    }
+    moves
+}
+
+/// Returns the ranges of the consecutive `true` flags
+fn blocks(flags: &[bool]) -> impl Iterator<Item = Range<usize>> + '_ {
+    let mut start = 0;
+    std::iter::from_fn(move || {
+        while start < flags.len() && !flags[start] {
+            start += 1;
+        }
+        let end = start + flags[start..].iter().take_while(|flag| **flag).count();
+        let block = start..end;
+        start = end;
+        Some(block).filter(|block| !block.is_empty())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Hirschberg, HirschbergAlg};
+
+    #[test]
+    fn test_detect_moves() {
+        let first = vec![
+            "fn a() {",
+            "    one();",
+            "    two();",
+            "}",
+            "",
+            "fn b() {",
+            "    three();",
+            "}",
+            "",
+            "fn c() {",
+            "    four();",
+            "    five();",
+            "}",
+        ];
+        let second = vec![
+            "fn c() {",
+            "    four();",
+            "    five!();",
+            "}",
+            "",
+            "fn b() {",
+            "    three();",
+            "}",
+            "",
+            "fn a() {",
+            "    one();",
+            "    two();",
+            "}",
+        ];
+        let diff: Vec<_> = Hirschberg::diff(first.clone(), second.clone());
+        let moves = detect_moves(&diff, 3, 0.6);
+        assert_eq!(
+            moves,
+            vec![
+                Move {
+                    id: 0,
+                    first: 9..12,
+                    second: 0..3,
+                    exact: false,
+                },
+                Move {
+                    id: 1,
+                    first: 0..3,
+                    second: 9..12,
+                    exact: true,
+                },
+            ]
+        );
+        assert_eq!(first[moves[1].first.clone()], second[moves[1].second.clone()]);
+        assert_eq!(detect_moves(&diff, 3, 0.7).len(), 1);
+
+        let diff = vec![
+            DiffItem::First("x"),
+            DiffItem::First("y"),
+            DiffItem::Both("z"),
+            DiffItem::Second("x"),
+            DiffItem::Second("y"),
+        ];
+        assert_eq!(detect_moves(&diff, 3, 0.7), vec![]);
+        assert_eq!(
+            detect_moves(&diff, 2, 0.7),
+            vec![Move {
+                id: 0,
+                first: 0..2,
+                second: 1..3,
+                exact: true,
+            }]
+        );
+
+        let diff = vec![DiffItem::First("x"), DiffItem::Second("y")];
+        assert_eq!(detect_moves(&diff, 1, 0.5), vec![]);
+    }
+}
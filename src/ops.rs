@@ -0,0 +1,240 @@
+use std::ops::Range;
+
+use crate::DiffItem;
+
+/// The kind of a `DiffOp`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum DiffTag {
+    Equal,
+    Delete,
+    Insert,
+    Replace,
+}
+
+impl DiffTag {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Equal => "equal",
+            Self::Delete => "delete",
+            Self::Insert => "insert",
+            Self::Replace => "replace",
+        }
+    }
+}
+
+/// A run of the diff that turns the `first` range of the first sequence
+/// into the `second` range of the second sequence, like the opcodes
+/// of Python's `difflib`. The range of the absent side is empty and placed
+/// where the items are deleted from or inserted to.
+///
+/// With the `serde` feature it is serialized as `{"op":"insert","old":[3,3],"new":[3,5]}`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DiffOp {
+    #[cfg_attr(feature = "serde", serde(rename = "op"))]
+    pub tag: DiffTag,
+    #[cfg_attr(feature = "serde", serde(rename = "old", with = "range"))]
+    pub first: Range<usize>,
+    #[cfg_attr(feature = "serde", serde(rename = "new", with = "range"))]
+    pub second: Range<usize>,
+}
+
+/// A group of the changes with the surrounding equal items
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Hunk {
+    #[cfg_attr(feature = "serde", serde(rename = "old", with = "range"))]
+    pub first: Range<usize>,
+    #[cfg_attr(feature = "serde", serde(rename = "new", with = "range"))]
+    pub second: Range<usize>,
+    pub ops: Vec<DiffOp>,
+}
+
+/// Serializes a range as the `[start, end]` pair
+#[cfg(feature = "serde")]
+pub(crate) mod range {
+    use std::ops::Range;
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(range: &Range<usize>, serializer: S) -> Result<S::Ok, S::Error> {
+        (range.start, range.end).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Range<usize>, D::Error> {
+        let (start, end) = <(usize, usize)>::deserialize(deserializer)?;
+        if start > end {
+            return Err(serde::de::Error::custom(format!(
+                "the range [{}, {}] is reversed",
+                start, end
+            )));
+        }
+        Ok(start..end)
+    }
+}
+
+/// Converts the diff into the runs of the equal, deleted and inserted items.
+/// The deletions and insertions between the same equal runs are joined
+/// into a single replacement.
+pub fn diff_ops<T>(diff: &[DiffItem<T>]) -> Vec<DiffOp> {
+    let mut ops: Vec<DiffOp> = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    for item in diff {
+        let (tag, di, dj) = match item {
+            DiffItem::First(_) => (DiffTag::Delete, 1, 0),
+            DiffItem::Both(_) => (DiffTag::Equal, 1, 1),
+            DiffItem::Second(_) => (DiffTag::Insert, 0, 1),
+        };
+
+        match ops.last_mut() {
+            Some(last) if last.tag == tag || (tag != DiffTag::Equal && last.tag != DiffTag::Equal) => {
+                if last.tag != tag {
+                    last.tag = DiffTag::Replace;
+                }
+                last.first.end += di;
+                last.second.end += dj;
+            }
+            _ => ops.push(DiffOp {
+                tag,
+                first: i..i + di,
+                second: j..j + dj,
+            }),
+        }
+        i += di;
+        j += dj;
+    }
+    ops
+}
+
+/// Groups the changes into the hunks with up to `context` equal items around
+/// them. The changes separated by at most `2 * context` equal items share a hunk.
+pub fn hunks(ops: &[DiffOp], context: usize) -> Vec<Hunk> {
+    let mut hunks = Vec::new();
+    let mut group: Vec<DiffOp> = Vec::new();
+
+    for (index, op) in ops.iter().enumerate() {
+        let mut op = op.clone();
+        if op.tag == DiffTag::Equal {
+            if index == 0 {
+                let skipped = op.first.len().saturating_sub(context);
+                op.first.start += skipped;
+                op.second.start += skipped;
+            }
+            if index + 1 == ops.len() {
+                op.first.end = op.first.end.min(op.first.start + context);
+                op.second.end = op.second.end.min(op.second.start + context);
+            }
+
+            if op.first.len() > 2 * context && !group.is_empty() {
+                if context > 0 {
+                    group.push(DiffOp {
+                        tag: DiffTag::Equal,
+                        first: op.first.start..op.first.start + context,
+                        second: op.second.start..op.second.start + context,
+                    });
+                }
+                hunks.push(into_hunk(group));
+                group = Vec::new();
+                op.first.start = op.first.end - context;
+                op.second.start = op.second.end - context;
+            }
+        }
+        if !op.first.is_empty() || !op.second.is_empty() {
+            group.push(op);
+        }
+    }
+
+    if group.iter().any(|op| op.tag != DiffTag::Equal) {
+        hunks.push(into_hunk(group));
+    }
+    hunks
+}
+
+fn into_hunk(ops: Vec<DiffOp>) -> Hunk {
+    let (first_op, last_op) = (&ops[0], &ops[ops.len() - 1]);
+    Hunk {
+        first: first_op.first.start..last_op.first.end,
+        second: first_op.second.start..last_op.second.end,
+        ops,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AsCharsIter, Hirschberg, HirschbergAlg};
+
+    fn op(tag: DiffTag, first: Range<usize>, second: Range<usize>) -> DiffOp {
+        DiffOp { tag, first, second }
+    }
+
+    #[test]
+    fn test_diff_ops() {
+        let diff: Vec<_> = Hirschberg::diff("abcdfghjqvz".chars_iter(), "abcdefgijkrxyz".chars_iter());
+        assert_eq!(
+            diff_ops(&diff),
+            vec![
+                op(DiffTag::Equal, 0..4, 0..4),
+                op(DiffTag::Insert, 4..4, 4..5),
+                op(DiffTag::Equal, 4..6, 5..7),
+                op(DiffTag::Replace, 6..7, 7..8),
+                op(DiffTag::Equal, 7..8, 8..9),
+                op(DiffTag::Replace, 8..10, 9..13),
+                op(DiffTag::Equal, 10..11, 13..14),
+            ]
+        );
+        assert_eq!(diff_ops::<char>(&[]), vec![]);
+    }
+
+    #[test]
+    fn test_hunks() {
+        let first: Vec<_> = (0..20).collect();
+        let mut second = first.clone();
+        second[2] = 100;
+        second.insert(15, 200);
+        let diff: Vec<_> = Hirschberg::diff(first.iter(), second.iter());
+        let ops = diff_ops(&diff);
+
+        let grouped = hunks(&ops, 3);
+        assert_eq!(grouped.len(), 2);
+        assert_eq!((grouped[0].first.clone(), grouped[0].second.clone()), (0..6, 0..6));
+        assert_eq!(
+            grouped[0].ops,
+            vec![
+                op(DiffTag::Equal, 0..2, 0..2),
+                op(DiffTag::Replace, 2..3, 2..3),
+                op(DiffTag::Equal, 3..6, 3..6),
+            ]
+        );
+        assert_eq!((grouped[1].first.clone(), grouped[1].second.clone()), (12..18, 12..19));
+
+        let grouped = hunks(&ops, 6);
+        assert_eq!(grouped.len(), 1);
+        assert_eq!((grouped[0].first.clone(), grouped[0].second.clone()), (0..20, 0..21));
+
+        let diff: Vec<_> = Hirschberg::diff(first.iter(), first.iter());
+        assert!(hunks(&diff_ops(&diff), 3).is_empty());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let ops = vec![op(DiffTag::Equal, 0..3, 0..3), op(DiffTag::Insert, 3..3, 3..5)];
+        let json = serde_json::to_string(&ops).unwrap();
+        assert_eq!(
+            json,
+            r#"[{"op":"equal","old":[0,3],"new":[0,3]},{"op":"insert","old":[3,3],"new":[3,5]}]"#
+        );
+        assert_eq!(serde_json::from_str::<Vec<DiffOp>>(&json).unwrap(), ops);
+
+        let hunk = into_hunk(ops);
+        let json = serde_json::to_string(&hunk).unwrap();
+        assert!(json.starts_with(r#"{"old":[0,3],"new":[0,5],"ops":[{"op":"equal""#));
+        assert_eq!(serde_json::from_str::<Hunk>(&json).unwrap(), hunk);
+
+        assert!(serde_json::from_str::<DiffOp>(r#"{"op":"delete","old":[2,1],"new":[1,1]}"#).is_err());
+        assert!(serde_json::from_str::<DiffOp>(r#"{"op":"move","old":[0,1],"new":[1,1]}"#).is_err());
+    }
+}
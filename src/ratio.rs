@@ -0,0 +1,182 @@
+//! The similarity ratios of sequences in the manner of Python's
+//! `difflib.SequenceMatcher`. The ratio is `2*M/T`, where `T` is the total
+//! length of both sequences and `M` is the length of their LCS, so it may be
+//! higher than the one of Python, which counts the matches found by
+//! the Ratcliff-Obershelp algorithm. The quick ratios are its cheaper upper bounds.
+
+use std::{collections::HashMap, hash::Hash};
+
+use crate::{score_last_line, SequenceIterator};
+
+/// This function returns the length of the longest common subsequence
+/// computed in linear space without the subsequence itself
+pub fn lcs_len<SeqA, SeqB>(a: SeqA, b: SeqB) -> usize
+where
+    SeqA: IntoIterator,
+    SeqB: IntoIterator,
+    SeqA::IntoIter: ExactSizeIterator,
+    SeqB::IntoIter: ExactSizeIterator + Clone,
+    SeqA::Item: PartialEq<SeqB::Item>,
+{
+    let line: Vec<usize> = score_last_line(a, b);
+    line[line.len() - 1]
+}
+
+/// Returns the similarity of the sequences from 0.0 to 1.0,
+/// the empty sequences are equal
+pub fn ratio<SeqA, SeqB>(a: SeqA, b: SeqB) -> f64
+where
+    SeqA: IntoIterator,
+    SeqB: IntoIterator,
+    SeqA::IntoIter: ExactSizeIterator,
+    SeqB::IntoIter: ExactSizeIterator + Clone,
+    SeqA::Item: PartialEq<SeqB::Item>,
+{
+    let (a, b) = (a.into_iter(), b.into_iter());
+    let total = a.len() + b.len();
+    calculate_ratio(lcs_len(a, b), total)
+}
+
+/// Returns an upper bound of the `ratio` that counts the common items
+/// regardless of their order in O(m + n) time
+pub fn quick_ratio<SeqA, SeqB>(a: SeqA, b: SeqB) -> f64
+where
+    SeqA: IntoIterator,
+    SeqB: IntoIterator<Item = SeqA::Item>,
+    SeqA::Item: Hash + Eq,
+{
+    let mut counts: HashMap<SeqA::Item, isize> = HashMap::new();
+    let mut total = 0;
+    for item in b {
+        *counts.entry(item).or_default() += 1;
+        total += 1;
+    }
+
+    let mut matches = 0;
+    for item in a {
+        total += 1;
+        if let Some(count) = counts.get_mut(&item) {
+            if *count > 0 {
+                matches += 1;
+            }
+            *count -= 1;
+        }
+    }
+    calculate_ratio(matches, total)
+}
+
+/// Returns an upper bound of the `quick_ratio` that depends only on the lengths
+/// of the sequences
+pub fn real_quick_ratio<SeqA, SeqB>(a: SeqA, b: SeqB) -> f64
+where
+    SeqA: IntoIterator,
+    SeqB: IntoIterator,
+    SeqA::IntoIter: ExactSizeIterator,
+    SeqB::IntoIter: ExactSizeIterator,
+{
+    let (a_len, b_len) = (a.into_iter().len(), b.into_iter().len());
+    calculate_ratio(a_len.min(b_len), a_len + b_len)
+}
+
+/// Returns at most `n` candidates, whose `ratio` with the `query` is at least
+/// the `cutoff`, from the most similar one. The equally similar candidates keep
+/// their order. The quick ratios filter out the candidates that cannot get
+/// into the result before the `ratio` is computed.
+pub fn close_matches<Q, I, C>(query: Q, candidates: I, n: usize, cutoff: f64) -> Vec<C>
+where
+    Q: IntoIterator,
+    Q::IntoIter: SequenceIterator,
+    Q::Item: Hash + Eq,
+    I: IntoIterator<Item = C>,
+    C: IntoIterator<Item = Q::Item> + Clone,
+    C::IntoIter: SequenceIterator,
+{
+    if n == 0 {
+        return Vec::new();
+    }
+    let query = query.into_iter();
+    let mut matches: Vec<(f64, C)> = Vec::with_capacity(n);
+
+    for candidate in candidates {
+        // A candidate has to beat the last of the `n` matches found so far
+        let is_passed = |score: f64| match matches.last() {
+            Some((last, _)) if matches.len() == n => score > *last,
+            _ => score >= cutoff,
+        };
+
+        let items = candidate.clone().into_iter();
+        if !is_passed(real_quick_ratio(items.clone(), query.clone()))
+            || !is_passed(quick_ratio(items.clone(), query.clone()))
+        {
+            continue;
+        }
+        let score = ratio(items, query.clone());
+        if is_passed(score) {
+            if matches.len() == n {
+                matches.pop();
+            }
+            let index = matches.partition_point(|(other, _)| *other >= score);
+            matches.insert(index, (score, candidate));
+        }
+    }
+    matches.into_iter().map(|(_, candidate)| candidate).collect()
+}
+
+fn calculate_ratio(matches: usize, total: usize) -> f64 {
+    if total > 0 {
+        2.0 * matches as f64 / total as f64
+    } else {
+        1.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AsCharsIter;
+
+    #[test]
+    fn test_ratio() {
+        assert_eq!(lcs_len("abcd".chars_iter(), "bcde".chars_iter()), 3);
+        assert_eq!(ratio("abcd".chars_iter(), "bcde".chars_iter()), 0.75);
+        assert_eq!(ratio(b"", b""), 1.0);
+        assert_eq!(ratio(b"abc", b""), 0.0);
+        assert_eq!(ratio(b"abc", b"abc"), 1.0);
+
+        // The examples from the documentation of difflib
+        assert_eq!(quick_ratio("abcd".chars(), "bcde".chars()), 0.75);
+        assert_eq!(real_quick_ratio("abcd".chars_iter(), "bcde".chars_iter()), 1.0);
+
+        assert_eq!(ratio("abc".chars_iter(), "cba".chars_iter()), 1.0 / 3.0);
+        assert_eq!(quick_ratio("abc".chars(), "cba".chars()), 1.0);
+        assert_eq!(quick_ratio("aab".chars(), "abb".chars()), 2.0 / 3.0);
+        assert_eq!(real_quick_ratio("ab".chars_iter(), "abcd".chars_iter()), 2.0 / 3.0);
+        assert_eq!(quick_ratio("".chars(), "".chars()), 1.0);
+    }
+
+    #[test]
+    fn test_close_matches() {
+        let words = ["ape", "apple", "peach", "puppy"];
+        let matches = close_matches("appel".chars_iter(), words.iter().map(|word| word.chars_iter()), 3, 0.6);
+        let matches: Vec<_> = matches.iter().map(|word| word.as_str()).collect();
+        assert_eq!(matches, vec!["apple", "ape"]);
+
+        let matches = close_matches("appel".chars_iter(), words.iter().map(|word| word.chars_iter()), 1, 0.6);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].as_str(), "apple");
+
+        let matches = close_matches("appel".chars_iter(), words.iter().map(|word| word.chars_iter()), 0, 0.6);
+        assert!(matches.is_empty());
+
+        let words = ["wheel", "while", "with", "whole"];
+        let matches = close_matches("whle".chars_iter(), words.iter().map(|word| word.chars_iter()), 4, 0.0);
+        let matches: Vec<_> = matches.iter().map(|word| word.as_str()).collect();
+        assert_eq!(matches, vec!["while", "whole", "wheel", "with"]);
+
+        // The sequences of tokens
+        let commands: [&[&str]; 3] = [&["git", "commit"], &["git", "checkout"], &["cargo", "check"]];
+        let query: &[&str] = &["git", "comit"];
+        let matches = close_matches(query, commands.iter().copied(), 2, 0.5);
+        assert_eq!(matches, vec![&["git", "commit"][..], &["git", "checkout"]]);
+    }
+}
@@ -84,15 +84,26 @@ pub trait Difference<T> {
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct Lcs<T>(pub T);
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct Diff<T>(pub T);
 
+/// An item of a diff. With the `serde` feature it is serialized as
+/// `{"op":"delete","value":x}`, where the `op` is `delete`, `equal` or `insert`.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "op", content = "value"))]
 pub enum DiffItem<T> {
+    #[cfg_attr(feature = "serde", serde(rename = "delete"))]
     First(T),
+    #[cfg_attr(feature = "serde", serde(rename = "equal"))]
     Both(T),
+    #[cfg_attr(feature = "serde", serde(rename = "insert"))]
     Second(T),
 }
 
@@ -146,6 +157,90 @@ impl<T, I: Insert<DiffItem<T>>> Difference<T> for Diff<I> {
     }
 }
 
+/// The interface of a difference that also keeps the substitutions
+/// of the items instead of splitting them into a deletion and an insertion
+pub trait EditScript<T>: Difference<T> {
+    fn push_replace(&mut self, first: T, second: T);
+}
+
+/// The edit script wrapper of an `Insert<EditItem<T>>`
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct Edits<T>(pub T);
+
+/// An item of an edit script, serialized like a `DiffItem`, and the replacement
+/// as `{"op":"replace","value":[x,y]}`
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "op", content = "value"))]
+pub enum EditItem<T> {
+    #[cfg_attr(feature = "serde", serde(rename = "delete"))]
+    First(T),
+    #[cfg_attr(feature = "serde", serde(rename = "equal"))]
+    Both(T),
+    #[cfg_attr(feature = "serde", serde(rename = "insert"))]
+    Second(T),
+    #[cfg_attr(feature = "serde", serde(rename = "replace"))]
+    Replace(T, T),
+}
+
+impl<T: fmt::Display> fmt::Display for EditItem<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EditItem::First(x) => write!(f, "- {}", x),
+            EditItem::Both(x) => write!(f, "{}", x),
+            EditItem::Second(x) => write!(f, "+ {}", x),
+            EditItem::Replace(x, y) => write!(f, "~ {} -> {}", x, y),
+        }
+    }
+}
+
+impl<T> From<DiffItem<T>> for EditItem<T> {
+    fn from(item: DiffItem<T>) -> Self {
+        match item {
+            DiffItem::First(x) => EditItem::First(x),
+            DiffItem::Both(x) => EditItem::Both(x),
+            DiffItem::Second(x) => EditItem::Second(x),
+        }
+    }
+}
+
+impl<T, I: Insert<T>> EditScript<T> for Lcs<I> {
+    fn push_replace(&mut self, _first: T, _second: T) {}
+}
+
+impl<T, I: Insert<DiffItem<T>>> EditScript<T> for Diff<I> {
+    fn push_replace(&mut self, first: T, second: T) {
+        self.push_first(first);
+        self.push_second(second);
+    }
+}
+
+impl<T, I: Insert<EditItem<T>>> Difference<T> for Edits<I> {
+    fn empty() -> Self {
+        Self(I::empty())
+    }
+
+    fn push_first(&mut self, item: T) {
+        self.0.insert(EditItem::First(item))
+    }
+
+    fn push_both(&mut self, item: T) {
+        self.0.insert(EditItem::Both(item))
+    }
+
+    fn push_second(&mut self, item: T) {
+        self.0.insert(EditItem::Second(item))
+    }
+}
+
+impl<T, I: Insert<EditItem<T>>> EditScript<T> for Edits<I> {
+    fn push_replace(&mut self, first: T, second: T) {
+        self.0.insert(EditItem::Replace(first, second))
+    }
+}
+
 pub trait SequenceIterator: ExactSizeIterator + DoubleEndedIterator + Clone {}
 
 impl<T: ExactSizeIterator + DoubleEndedIterator + Clone> SequenceIterator for T {}
@@ -366,6 +461,29 @@ impl<I: DoubleEndedIterator + ExactSizeIterator + FusedIterator> FusedIterator f
 mod tests {
     use super::*;
 
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let diff: Diff<Vec<DiffItem<char>>> =
+            Diff(vec![DiffItem::Both('a'), DiffItem::First('b'), DiffItem::Second('c')]);
+        let json = serde_json::to_string(&diff).unwrap();
+        assert_eq!(
+            json,
+            r#"[{"op":"equal","value":"a"},{"op":"delete","value":"b"},{"op":"insert","value":"c"}]"#
+        );
+        let Diff(items): Diff<Vec<DiffItem<char>>> = serde_json::from_str(&json).unwrap();
+        assert_eq!(items, diff.0);
+
+        let lcs = Lcs("ab".to_string());
+        assert_eq!(serde_json::to_string(&lcs).unwrap(), r#""ab""#);
+
+        let edits = Edits(vec![EditItem::Replace(1, 2), EditItem::Both(3)]);
+        let json = serde_json::to_string(&edits).unwrap();
+        assert_eq!(json, r#"[{"op":"replace","value":[1,2]},{"op":"equal","value":3}]"#);
+        let Edits(items): Edits<Vec<EditItem<i32>>> = serde_json::from_str(&json).unwrap();
+        assert_eq!(items, edits.0);
+    }
+
     #[test]
     fn test_seq_iter() {
         let a = [1, 2, 3];
@@ -0,0 +1,336 @@
+//! The post-processing of line diffs that slides the blocks of inserted
+//! or deleted lines to the positions that follow the structure of the text.
+//!
+//! A block can slide up when the line before it equals its last line, and down
+//! when the line after it equals its first line, without changing the diff
+//! otherwise. The sliders below consider only the pure insertions or deletions
+//! surrounded by the equal lines, like git's `xdl_change_compact`.
+
+use crate::DiffItem;
+
+const START_OF_FILE_PENALTY: i32 = 1;
+const END_OF_FILE_PENALTY: i32 = 21;
+const TOTAL_BLANK_WEIGHT: i32 = -30;
+const POST_BLANK_WEIGHT: i32 = 6;
+const RELATIVE_INDENT_PENALTY: i32 = -4;
+const RELATIVE_INDENT_WITH_BLANK_PENALTY: i32 = 10;
+const RELATIVE_OUTDENT_PENALTY: i32 = 24;
+const RELATIVE_OUTDENT_WITH_BLANK_PENALTY: i32 = 17;
+const RELATIVE_DEDENT_PENALTY: i32 = 23;
+const RELATIVE_DEDENT_WITH_BLANK_PENALTY: i32 = 17;
+const INDENT_WEIGHT: i32 = 60;
+
+/// The maximum number of the positions tried by the indent heuristic
+const INDENT_HEURISTIC_MAX_SLIDING: usize = 100;
+/// The maximum number of the blank lines looked through around a split
+const MAX_BLANKS: i32 = 20;
+/// The indent that all the larger ones are truncated to
+const MAX_INDENT: i32 = 200;
+
+/// Slides every block to the lowest position where it ends with a blank
+/// line, or as far down as possible if there is no such position
+pub fn slide_blank_lines<T: AsRef<str> + PartialEq>(mut diff: Vec<DiffItem<T>>) -> Vec<DiffItem<T>> {
+    slide(&mut diff, |indents, _size, ends| {
+        ends.clone()
+            .rev()
+            .find(|&end| indents[end - 1].is_none())
+            .unwrap_or(ends.end - 1)
+    });
+    diff
+}
+
+/// Slides every block to the position chosen by git's indent heuristic,
+/// which prefers the blocks that start and end at the blank lines and at
+/// the lines that are indented less than their neighbours
+pub fn slide_indent_heuristic<T: AsRef<str> + PartialEq>(mut diff: Vec<DiffItem<T>>) -> Vec<DiffItem<T>> {
+    slide(&mut diff, |indents, size, ends| {
+        let last = ends.end - 1;
+        let first = ends
+            .start
+            .max(last.saturating_sub(size + 1))
+            .max(last.saturating_sub(INDENT_HEURISTIC_MAX_SLIDING));
+
+        let mut best: Option<(usize, Score)> = None;
+        for end in first..=last {
+            let mut score = Score::default();
+            score.add(&Split::measure(indents, end));
+            score.add(&Split::measure(indents, end - size));
+            if best.map_or(true, |(_, best_score)| score.cmp(&best_score) <= 0) {
+                best = Some((end, score));
+            }
+        }
+        best.map_or(last, |(end, _)| end)
+    });
+    diff
+}
+
+/// Slides the blocks of the insertions, then the blocks of the deletions.
+/// The `choose` closure gets the indents of the lines of the side of the blocks
+/// (`None` for the blank lines), the size of a block and the range of the possible
+/// ends of it, and returns the chosen end.
+fn slide<T, F>(diff: &mut [DiffItem<T>], mut choose: F)
+where
+    T: AsRef<str> + PartialEq,
+    F: FnMut(&[Option<i32>], usize, std::ops::Range<usize>) -> usize,
+{
+    for is_insertion in [true, false] {
+        let is_change = |item: &DiffItem<T>| match item {
+            DiffItem::First(_) => !is_insertion,
+            DiffItem::Second(_) => is_insertion,
+            DiffItem::Both(_) => false,
+        };
+        let is_both = |item: &DiffItem<T>| matches!(item, DiffItem::Both(_));
+
+        let mut indents = Vec::new();
+        // The positions of the side lines that precede the diff items
+        let mut positions = Vec::with_capacity(diff.len());
+        for item in diff.iter() {
+            positions.push(indents.len());
+            if is_change(item) || is_both(item) {
+                indents.push(indent(item_value(item).as_ref()));
+            }
+        }
+
+        let mut start = 0;
+        while start < diff.len() {
+            if !is_change(&diff[start]) {
+                start += 1;
+                continue;
+            }
+            let mut end = start;
+            while end < diff.len() && is_change(&diff[end]) {
+                end += 1;
+            }
+            let is_pure = (start == 0 || is_both(&diff[start - 1])) && (end == diff.len() || is_both(&diff[end]));
+            if !is_pure {
+                start = end;
+                continue;
+            }
+
+            while start > 0 && is_both(&diff[start - 1]) && item_value(&diff[start - 1]) == item_value(&diff[end - 1]) {
+                diff.swap(start - 1, end - 1);
+                start -= 1;
+                end -= 1;
+            }
+            let earliest_end = end;
+            while end < diff.len() && is_both(&diff[end]) && item_value(&diff[end]) == item_value(&diff[start]) {
+                diff.swap(start, end);
+                start += 1;
+                end += 1;
+            }
+
+            if end > earliest_end {
+                // The block moves by the same number of the diff items and the side lines
+                let side_end = positions[start] + (end - start);
+                let side_earliest_end = side_end - (end - earliest_end);
+                let chosen = choose(&indents, end - start, side_earliest_end..side_end + 1);
+                for _ in chosen..side_end {
+                    start -= 1;
+                    end -= 1;
+                    diff.swap(start, end);
+                }
+            }
+            start = end;
+        }
+    }
+}
+
+fn item_value<T>(item: &DiffItem<T>) -> &T {
+    match item {
+        DiffItem::First(x) | DiffItem::Both(x) | DiffItem::Second(x) => x,
+    }
+}
+
+/// Returns the width of the leading whitespace of the `line` with the tabs
+/// expanded to the multiples of 8, or `None` if the line is blank
+fn indent(line: &str) -> Option<i32> {
+    let mut indent = 0;
+    for c in line.chars() {
+        match c {
+            ' ' => indent += 1,
+            '\t' => indent += 8 - indent % 8,
+            c if c.is_whitespace() => (),
+            _ => return Some(indent),
+        }
+        if indent >= MAX_INDENT {
+            return Some(MAX_INDENT);
+        }
+    }
+    None
+}
+
+/// The characteristics of the split of the lines before the `split` position
+/// from the rest of them, -1 stands for the blank line or no line
+struct Split {
+    end_of_file: bool,
+    indent: i32,
+    pre_blank: i32,
+    pre_indent: i32,
+    post_blank: i32,
+    post_indent: i32,
+}
+
+impl Split {
+    fn measure(indents: &[Option<i32>], split: usize) -> Self {
+        let end_of_file = split >= indents.len();
+        let indent = if end_of_file { -1 } else { indents[split].unwrap_or(-1) };
+
+        let (mut pre_blank, mut pre_indent) = (0, -1);
+        for line_indent in indents[..split].iter().rev() {
+            if let Some(indent) = *line_indent {
+                pre_indent = indent;
+                break;
+            }
+            pre_blank += 1;
+            if pre_blank == MAX_BLANKS {
+                pre_indent = 0;
+                break;
+            }
+        }
+
+        let (mut post_blank, mut post_indent) = (0, -1);
+        for line_indent in indents.iter().skip(split + 1) {
+            if let Some(indent) = *line_indent {
+                post_indent = indent;
+                break;
+            }
+            post_blank += 1;
+            if post_blank == MAX_BLANKS {
+                post_indent = 0;
+                break;
+            }
+        }
+
+        Self {
+            end_of_file,
+            indent,
+            pre_blank,
+            pre_indent,
+            post_blank,
+            post_indent,
+        }
+    }
+}
+
+/// The score of a position of a block, the lower the better
+#[derive(Clone, Copy, Default)]
+struct Score {
+    effective_indent: i32,
+    penalty: i32,
+}
+
+impl Score {
+    fn add(&mut self, split: &Split) {
+        if split.pre_indent == -1 && split.pre_blank == 0 {
+            self.penalty += START_OF_FILE_PENALTY;
+        }
+        if split.end_of_file {
+            self.penalty += END_OF_FILE_PENALTY;
+        }
+
+        let post_blank = if split.indent == -1 { 1 + split.post_blank } else { 0 };
+        let total_blank = split.pre_blank + post_blank;
+        self.penalty += TOTAL_BLANK_WEIGHT * total_blank;
+        self.penalty += POST_BLANK_WEIGHT * post_blank;
+
+        let indent = if split.indent != -1 {
+            split.indent
+        } else {
+            split.post_indent
+        };
+        let any_blanks = total_blank != 0;
+        self.effective_indent += indent;
+
+        if indent == -1 || split.pre_indent == -1 || indent == split.pre_indent {
+            // No adjustments
+        } else if indent > split.pre_indent {
+            self.penalty += if any_blanks {
+                RELATIVE_INDENT_WITH_BLANK_PENALTY
+            } else {
+                RELATIVE_INDENT_PENALTY
+            };
+        } else if split.post_indent != -1 && split.post_indent > indent {
+            self.penalty += if any_blanks {
+                RELATIVE_OUTDENT_WITH_BLANK_PENALTY
+            } else {
+                RELATIVE_OUTDENT_PENALTY
+            };
+        } else {
+            self.penalty += if any_blanks {
+                RELATIVE_DEDENT_WITH_BLANK_PENALTY
+            } else {
+                RELATIVE_DEDENT_PENALTY
+            };
+        }
+    }
+
+    fn cmp(&self, other: &Self) -> i32 {
+        let indents = (self.effective_indent > other.effective_indent) as i32
+            - (self.effective_indent < other.effective_indent) as i32;
+        INDENT_WEIGHT * indents + (self.penalty - other.penalty)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Hirschberg, HirschbergAlg};
+
+    fn format_diff(diff: &[DiffItem<&str>]) -> String {
+        let mut text = String::new();
+        for item in diff {
+            let prefix = match item {
+                DiffItem::First(_) => '-',
+                DiffItem::Both(_) => ' ',
+                DiffItem::Second(_) => '+',
+            };
+            text.push(prefix);
+            text.push_str(item_value::<&str>(item));
+            text.push('\n');
+        }
+        text
+    }
+
+    const FIRST: &str = "fn a() {\n    x();\n}\n\nfn c() {\n    z();\n}";
+    const SECOND: &str = "fn a() {\n    x();\n}\n\nfn b() {\n    y();\n}\n\nfn c() {\n    z();\n}";
+
+    #[test]
+    fn test_slide_indent_heuristic() {
+        let diff: Vec<_> = Hirschberg::diff(FIRST.lines().collect::<Vec<_>>(), SECOND.lines().collect::<Vec<_>>());
+        assert_eq!(
+            format_diff(&slide_indent_heuristic(diff)),
+            " fn a() {\n     x();\n }\n \n+fn b() {\n+    y();\n+}\n+\n fn c() {\n     z();\n }\n"
+        );
+
+        let first = vec!["if x {", "    a();", "    b();", "}"];
+        let second = vec!["if x {", "    a();", "    b();", "    a();", "    b();", "}"];
+        let diff: Vec<_> = Hirschberg::diff(first, second);
+        assert_eq!(
+            format_diff(&slide_indent_heuristic(diff)),
+            " if x {\n     a();\n     b();\n+    a();\n+    b();\n }\n"
+        );
+
+        let diff = vec![DiffItem::Both("a"), DiffItem::First("b"), DiffItem::Second("c")];
+        assert_eq!(slide_indent_heuristic(diff.clone()), diff);
+    }
+
+    #[test]
+    fn test_slide_blank_lines() {
+        let diff = vec![
+            DiffItem::Both("a"),
+            DiffItem::First("b"),
+            DiffItem::First(""),
+            DiffItem::Both("b"),
+            DiffItem::Both("c"),
+        ];
+        assert_eq!(format_diff(&slide_blank_lines(diff)), " a\n-b\n-\n b\n c\n");
+
+        let diff = vec![
+            DiffItem::Both("a"),
+            DiffItem::First("x"),
+            DiffItem::First("a"),
+            DiffItem::Both("x"),
+        ];
+        assert_eq!(format_diff(&slide_blank_lines(diff)), " a\n x\n-a\n-x\n");
+    }
+}
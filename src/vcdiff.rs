@@ -0,0 +1,810 @@
+//! The VCDIFF generic differencing and compression data format (RFC 3284)
+//! with the default code table and address cache. The encoder writes
+//! a single window whose source segment is the whole source and whose
+//! instructions come from the `Delta` encoder, with the repeated inserted
+//! bytes turned into runs. The decoder accepts any windows without secondary
+//! compression, including the Adler-32 checksums of the windows that
+//! `xdelta3` writes.
+//!
+//! ```
+//! use ng_diff::vcdiff;
+//!
+//! let (source, target) = (b"the quick brown fox jumps", b"the quick red fox jumps over");
+//! let delta = vcdiff::encode(source, target);
+//! assert_eq!(vcdiff::decode(source, &delta), Ok(target.to_vec()));
+//! ```
+
+use std::{borrow::Cow, collections::HashMap, error::Error, fmt, iter};
+
+use crate::{Delta, DeltaOp};
+
+const MAGIC: [u8; 4] = [0xd6, 0xc3, 0xc4, 0x00];
+
+// The bits of the header indicator
+const VCD_DECOMPRESS: u8 = 0x01;
+const VCD_CODETABLE: u8 = 0x02;
+const VCD_APPHEADER: u8 = 0x04;
+
+// The bits of the window indicator, the checksum is the extension of `xdelta3`
+const VCD_SOURCE: u8 = 0x01;
+const VCD_TARGET: u8 = 0x02;
+const VCD_ADLER32: u8 = 0x04;
+
+const NEAR_LEN: usize = 4;
+const SAME_LEN: usize = 3;
+const MODES: u8 = 2 + NEAR_LEN as u8 + SAME_LEN as u8;
+const VCD_SELF: u8 = 0;
+const VCD_HERE: u8 = 1;
+
+/// The shortest repetition of an inserted byte that is encoded as a run
+const MIN_RUN_LEN: usize = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum InstKind {
+    Noop,
+    Add,
+    Run,
+    Copy,
+}
+
+/// An instruction of the code table, the zero size is read from the instructions
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct Inst {
+    kind: InstKind,
+    size: usize,
+    mode: u8,
+}
+
+impl Inst {
+    fn new(kind: InstKind, size: usize, mode: u8) -> Self {
+        Self { kind, size, mode }
+    }
+}
+
+/// Builds the default code table of RFC 3284, section 5.6
+fn code_table() -> Vec<(Inst, Inst)> {
+    let noop = Inst::new(InstKind::Noop, 0, 0);
+    let mut table = vec![(Inst::new(InstKind::Run, 0, 0), noop)];
+    for size in 0..=17 {
+        table.push((Inst::new(InstKind::Add, size, 0), noop));
+    }
+    for mode in 0..MODES {
+        for size in iter::once(0).chain(4..=18) {
+            table.push((Inst::new(InstKind::Copy, size, mode), noop));
+        }
+    }
+    for mode in 0..6 {
+        for add_size in 1..=4 {
+            for copy_size in 4..=6 {
+                table.push((
+                    Inst::new(InstKind::Add, add_size, 0),
+                    Inst::new(InstKind::Copy, copy_size, mode),
+                ));
+            }
+        }
+    }
+    for mode in 6..MODES {
+        for add_size in 1..=4 {
+            table.push((
+                Inst::new(InstKind::Add, add_size, 0),
+                Inst::new(InstKind::Copy, 4, mode),
+            ));
+        }
+    }
+    for mode in 0..MODES {
+        table.push((Inst::new(InstKind::Copy, 4, mode), Inst::new(InstKind::Add, 1, 0)));
+    }
+    table
+}
+
+/// The caches of the recent copy addresses that make the addresses shorter
+struct AddressCache {
+    near: [usize; NEAR_LEN],
+    next_slot: usize,
+    same: [usize; SAME_LEN * 256],
+}
+
+impl AddressCache {
+    fn new() -> Self {
+        Self {
+            near: [0; NEAR_LEN],
+            next_slot: 0,
+            same: [0; SAME_LEN * 256],
+        }
+    }
+
+    fn update(&mut self, address: usize) {
+        self.near[self.next_slot] = address;
+        self.next_slot = (self.next_slot + 1) % NEAR_LEN;
+        self.same[address % (SAME_LEN * 256)] = address;
+    }
+
+    /// Returns the cheapest mode of the address and writes its value
+    fn encode(&mut self, address: usize, here: usize, addresses: &mut Vec<u8>) -> u8 {
+        let same_index = address % (SAME_LEN * 256);
+        let mode = if self.same[same_index] == address {
+            addresses.push((same_index % 256) as u8);
+            2 + NEAR_LEN as u8 + (same_index / 256) as u8
+        } else {
+            let near = self
+                .near
+                .iter()
+                .enumerate()
+                .filter(|(_, near)| **near <= address)
+                .map(|(slot, near)| (2 + slot as u8, address - near));
+            let (mode, value) = iter::once((VCD_SELF, address))
+                .chain(iter::once((VCD_HERE, here - address)))
+                .chain(near)
+                .min_by_key(|(_, value)| int_len(*value))
+                .unwrap_or((VCD_SELF, address));
+            write_int(addresses, value);
+            mode
+        };
+        self.update(address);
+        mode
+    }
+
+    fn decode(&mut self, mode: u8, here: usize, addresses: &mut Reader) -> Result<usize, VcdiffError> {
+        let address = match mode {
+            VCD_SELF => addresses.int()?,
+            VCD_HERE => here.checked_sub(addresses.int()?).ok_or(VcdiffError::InvalidAddress)?,
+            mode if mode < 2 + NEAR_LEN as u8 => self.near[(mode - 2) as usize]
+                .checked_add(addresses.int()?)
+                .ok_or(VcdiffError::Overflow)?,
+            mode if mode < MODES => {
+                let index = (mode - 2 - NEAR_LEN as u8) as usize * 256 + addresses.byte()? as usize;
+                self.same[index]
+            }
+            _ => return Err(VcdiffError::InvalidAddress),
+        };
+        if address >= here {
+            return Err(VcdiffError::InvalidAddress);
+        }
+        self.update(address);
+        Ok(address)
+    }
+}
+
+/// Encodes the target as a VCDIFF delta from the source
+pub fn encode(source: &[u8], target: &[u8]) -> Vec<u8> {
+    let mut out = MAGIC.to_vec();
+    out.push(0);
+    if target.is_empty() {
+        return out;
+    }
+
+    let table = code_table();
+    let mut singles = HashMap::new();
+    let mut pairs = HashMap::new();
+    for (index, (first, second)) in table.iter().enumerate() {
+        if second.kind == InstKind::Noop {
+            singles.insert(*first, index as u8);
+        } else {
+            pairs.insert((*first, *second), index as u8);
+        }
+    }
+
+    let (mut data, mut instructions, mut addresses) = (Vec::new(), Vec::new(), Vec::new());
+    let mut cache = AddressCache::new();
+    let mut pending: Option<Inst> = None;
+    let mut position = 0;
+
+    let mut push = |inst: Inst, instructions: &mut Vec<u8>| {
+        if let Some(last) = pending.take() {
+            if let Some(index) = pairs.get(&(last, inst)) {
+                instructions.push(*index);
+                return;
+            }
+            write_single(&singles, last, instructions);
+        }
+        pending = Some(inst);
+    };
+
+    for op in Delta::encode(source, target).ops() {
+        match op {
+            DeltaOp::Copy { offset, len } => {
+                let here = source.len() + position;
+                let mode = cache.encode(*offset, here, &mut addresses);
+                push(Inst::new(InstKind::Copy, *len, mode), &mut instructions);
+                position += len;
+            }
+            DeltaOp::Insert(bytes) => {
+                let mut start = 0;
+                while start < bytes.len() {
+                    let (add_end, run_len) = next_run(bytes, start);
+                    if add_end > start {
+                        data.extend_from_slice(&bytes[start..add_end]);
+                        push(Inst::new(InstKind::Add, add_end - start, 0), &mut instructions);
+                    }
+                    if run_len > 0 {
+                        data.push(bytes[add_end]);
+                        push(Inst::new(InstKind::Run, run_len, 0), &mut instructions);
+                    }
+                    start = add_end + run_len;
+                }
+                position += bytes.len();
+            }
+        }
+    }
+    if let Some(last) = pending {
+        write_single(&singles, last, &mut instructions);
+    }
+
+    let mut window = Vec::new();
+    write_int(&mut window, target.len());
+    window.push(0);
+    write_int(&mut window, data.len());
+    write_int(&mut window, instructions.len());
+    write_int(&mut window, addresses.len());
+    window.extend_from_slice(&data);
+    window.extend_from_slice(&instructions);
+    window.extend_from_slice(&addresses);
+
+    if source.is_empty() {
+        out.push(0);
+    } else {
+        out.push(VCD_SOURCE);
+        write_int(&mut out, source.len());
+        write_int(&mut out, 0);
+    }
+    write_int(&mut out, window.len());
+    out.extend_from_slice(&window);
+    out
+}
+
+/// Returns the end of the bytes before the next run of the same byte
+/// and the length of that run, which is zero if there is no run
+fn next_run(bytes: &[u8], start: usize) -> (usize, usize) {
+    let mut index = start;
+    while index < bytes.len() {
+        let run_len = bytes[index..].iter().take_while(|byte| **byte == bytes[index]).count();
+        if run_len >= MIN_RUN_LEN {
+            return (index, run_len);
+        }
+        index += run_len;
+    }
+    (bytes.len(), 0)
+}
+
+fn write_single(singles: &HashMap<Inst, u8>, inst: Inst, instructions: &mut Vec<u8>) {
+    match singles.get(&inst) {
+        Some(index) => instructions.push(*index),
+        None => {
+            instructions.push(singles[&Inst { size: 0, ..inst }]);
+            write_int(instructions, inst.size);
+        }
+    }
+}
+
+/// Decodes the target from the source and the VCDIFF delta
+pub fn decode(source: &[u8], delta: &[u8]) -> Result<Vec<u8>, VcdiffError> {
+    let mut reader = Reader::new(delta);
+    if reader.take(MAGIC.len())? != MAGIC {
+        return Err(VcdiffError::InvalidHeader);
+    }
+    let indicator = reader.byte()?;
+    if indicator & VCD_DECOMPRESS != 0 {
+        return Err(VcdiffError::Unsupported("secondary compression"));
+    }
+    if indicator & VCD_CODETABLE != 0 {
+        return Err(VcdiffError::Unsupported("application-defined code table"));
+    }
+    if indicator & !VCD_APPHEADER != 0 {
+        return Err(VcdiffError::InvalidHeader);
+    }
+    if indicator & VCD_APPHEADER != 0 {
+        let len = reader.int()?;
+        reader.take(len)?;
+    }
+
+    let table = code_table();
+    let mut target = Vec::new();
+    while !reader.is_empty() {
+        decode_window(source, &mut reader, &table, &mut target)?;
+    }
+    Ok(target)
+}
+
+fn decode_window(
+    source: &[u8], reader: &mut Reader, table: &[(Inst, Inst)], target: &mut Vec<u8>,
+) -> Result<(), VcdiffError> {
+    let indicator = reader.byte()?;
+    if indicator & !(VCD_SOURCE | VCD_TARGET | VCD_ADLER32) != 0
+        || indicator & (VCD_SOURCE | VCD_TARGET) == VCD_SOURCE | VCD_TARGET
+    {
+        return Err(VcdiffError::InvalidHeader);
+    }
+    let segment: Cow<[u8]> = if indicator & (VCD_SOURCE | VCD_TARGET) != 0 {
+        let (len, position) = (reader.int()?, reader.int()?);
+        let range = position..position.checked_add(len).ok_or(VcdiffError::Overflow)?;
+        if indicator & VCD_SOURCE != 0 {
+            Cow::Borrowed(source.get(range).ok_or(VcdiffError::InvalidSegment)?)
+        } else {
+            Cow::Owned(target.get(range).ok_or(VcdiffError::InvalidSegment)?.to_vec())
+        }
+    } else {
+        Cow::Borrowed(&[])
+    };
+
+    let len = reader.int()?;
+    let mut window = Reader::new(reader.take(len)?);
+    let target_len = window.int()?;
+    if window.byte()? != 0 {
+        return Err(VcdiffError::Unsupported("compressed sections"));
+    }
+    let (data_len, instructions_len, addresses_len) = (window.int()?, window.int()?, window.int()?);
+    let checksum = if indicator & VCD_ADLER32 != 0 {
+        let bytes = window.take(4)?;
+        Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    } else {
+        None
+    };
+    let mut data = Reader::new(window.take(data_len)?);
+    let mut instructions = Reader::new(window.take(instructions_len)?);
+    let mut addresses = Reader::new(window.take(addresses_len)?);
+    if !window.is_empty() {
+        return Err(VcdiffError::TrailingData);
+    }
+
+    let mut output = Vec::with_capacity(target_len.min(1 << 24));
+    let mut cache = AddressCache::new();
+    while !instructions.is_empty() {
+        let (first, second) = table[instructions.byte()? as usize];
+        for mut inst in [first, second] {
+            if inst.kind == InstKind::Noop {
+                continue;
+            }
+            if inst.size == 0 {
+                inst.size = instructions.int()?;
+            }
+            let end = output.len().checked_add(inst.size).ok_or(VcdiffError::Overflow)?;
+            if end > target_len {
+                return Err(VcdiffError::TargetLen {
+                    expected: target_len,
+                    actual: end,
+                });
+            }
+
+            match inst.kind {
+                InstKind::Add => output.extend_from_slice(data.take(inst.size)?),
+                InstKind::Run => {
+                    let byte = data.byte()?;
+                    output.resize(end, byte);
+                }
+                InstKind::Copy => {
+                    let here = segment.len() + output.len();
+                    let address = cache.decode(inst.mode, here, &mut addresses)?;
+                    let address_end = address.checked_add(inst.size).ok_or(VcdiffError::Overflow)?;
+                    // The copy from the target may overlap the bytes that it produces
+                    for index in address..address_end {
+                        let byte = match segment.get(index) {
+                            Some(byte) => *byte,
+                            None => output[index - segment.len()],
+                        };
+                        output.push(byte);
+                    }
+                }
+                InstKind::Noop => (),
+            }
+        }
+    }
+
+    if output.len() != target_len {
+        return Err(VcdiffError::TargetLen {
+            expected: target_len,
+            actual: output.len(),
+        });
+    }
+    if !data.is_empty() || !addresses.is_empty() {
+        return Err(VcdiffError::TrailingData);
+    }
+    if let Some(expected) = checksum {
+        let actual = adler32(&output);
+        if actual != expected {
+            return Err(VcdiffError::Checksum { expected, actual });
+        }
+    }
+    target.extend_from_slice(&output);
+    Ok(())
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, position: 0 }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.position == self.bytes.len()
+    }
+
+    fn byte(&mut self) -> Result<u8, VcdiffError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], VcdiffError> {
+        let bytes = self
+            .position
+            .checked_add(len)
+            .and_then(|end| self.bytes.get(self.position..end))
+            .ok_or(VcdiffError::Truncated)?;
+        self.position += len;
+        Ok(bytes)
+    }
+
+    /// Reads the integer by 7 bits from the highest ones, the high bit marks
+    /// that more bytes follow
+    fn int(&mut self) -> Result<usize, VcdiffError> {
+        let mut value = 0usize;
+        loop {
+            let byte = self.byte()?;
+            value = value
+                .checked_mul(0x80)
+                .map(|value| value | (byte & 0x7f) as usize)
+                .ok_or(VcdiffError::Overflow)?;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+    }
+}
+
+fn write_int(bytes: &mut Vec<u8>, value: usize) {
+    let start = bytes.len();
+    let mut value = value;
+    bytes.push((value & 0x7f) as u8);
+    value >>= 7;
+    while value > 0 {
+        bytes.insert(start, (value & 0x7f) as u8 | 0x80);
+        value >>= 7;
+    }
+}
+
+fn int_len(mut value: usize) -> usize {
+    let mut len = 1;
+    while value >= 0x80 {
+        value >>= 7;
+        len += 1;
+    }
+    len
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    const MOD: u32 = 65521;
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in bytes.chunks(5552) {
+        for byte in chunk {
+            a += *byte as u32;
+            b += a;
+        }
+        a %= MOD;
+        b %= MOD;
+    }
+    (b << 16) | a
+}
+
+/// An error of decoding a VCDIFF delta
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VcdiffError {
+    /// The delta does not start with the VCDIFF magic or has unknown indicator bits
+    InvalidHeader,
+    /// The delta uses a feature that is not supported
+    Unsupported(&'static str),
+    /// The delta ends in the middle of a window or a section
+    Truncated,
+    /// An integer does not fit in `usize`
+    Overflow,
+    /// The source segment of a window is out of the source or the target
+    InvalidSegment,
+    /// A copy address is out of the data decoded so far
+    InvalidAddress,
+    /// The instructions build a window of another length than its header says
+    TargetLen { expected: usize, actual: usize },
+    /// A window or a section has the bytes that no instruction uses
+    TrailingData,
+    /// The Adler-32 checksum of a window does not match
+    Checksum { expected: u32, actual: u32 },
+}
+
+impl fmt::Display for VcdiffError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidHeader => write!(f, "invalid VCDIFF header"),
+            Self::Unsupported(feature) => write!(f, "unsupported VCDIFF feature: {}", feature),
+            Self::Truncated => write!(f, "the VCDIFF delta is truncated"),
+            Self::Overflow => write!(f, "a VCDIFF integer overflows"),
+            Self::InvalidSegment => write!(f, "the VCDIFF source segment is out of range"),
+            Self::InvalidAddress => write!(f, "the VCDIFF copy address is out of range"),
+            Self::TargetLen { expected, actual } => {
+                write!(f, "the VCDIFF window builds {} bytes instead of {}", actual, expected)
+            }
+            Self::TrailingData => write!(f, "the VCDIFF window has unused bytes"),
+            Self::Checksum { expected, actual } => {
+                write!(f, "the VCDIFF checksum {:08x} does not match {:08x}", actual, expected)
+            }
+        }
+    }
+}
+
+impl Error for VcdiffError {}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        env,
+        ffi::OsStr,
+        fs,
+        path::{Path, PathBuf},
+        process::{self, Command},
+    };
+
+    use super::*;
+
+    const SOURCE: &[u8] = b"abcdefghijklmnop";
+    const TARGET: &[u8] = b"abcdwxyzefghefghefghefghzzzz";
+
+    /// The example of RFC 3284, section 3, encoded by hand:
+    /// COPY 4,0; ADD 4,wxyz; COPY 4,4; COPY 12,24; RUN 4,z
+    fn rfc_window(indicator: u8, checksum: Option<[u8; 4]>) -> Vec<u8> {
+        let mut window = vec![indicator, 16, 0, if checksum.is_some() { 22 } else { 18 }];
+        window.extend_from_slice(&[28, 0, 5, 5, 3]);
+        if let Some(checksum) = checksum {
+            window.extend_from_slice(&checksum);
+        }
+        // The data, the instructions: COPY 4 in the self mode, the pair of ADD 4
+        // and COPY 4 in the self mode, COPY 12 in the here mode and RUN of size 4,
+        // and the addresses
+        window.extend_from_slice(b"wxyzz");
+        window.extend_from_slice(&[20, 172, 44, 0, 4]);
+        window.extend_from_slice(&[0, 4, 4]);
+        window
+    }
+
+    #[test]
+    fn test_code_table() {
+        let table = code_table();
+        assert_eq!(table.len(), 256);
+        assert_eq!(table[0].0, Inst::new(InstKind::Run, 0, 0));
+        assert_eq!(table[18].0, Inst::new(InstKind::Add, 17, 0));
+        assert_eq!(table[19].0, Inst::new(InstKind::Copy, 0, 0));
+        assert_eq!(table[162].0, Inst::new(InstKind::Copy, 18, 8));
+        assert_eq!(
+            table[174],
+            (Inst::new(InstKind::Add, 4, 0), Inst::new(InstKind::Copy, 6, 0))
+        );
+        assert_eq!(
+            table[246],
+            (Inst::new(InstKind::Add, 4, 0), Inst::new(InstKind::Copy, 4, 8))
+        );
+        assert_eq!(
+            table[255],
+            (Inst::new(InstKind::Copy, 4, 8), Inst::new(InstKind::Add, 1, 0))
+        );
+    }
+
+    #[test]
+    fn test_decode() {
+        let mut delta = MAGIC.to_vec();
+        delta.push(0);
+        delta.extend_from_slice(&rfc_window(VCD_SOURCE, None));
+        assert_eq!(decode(SOURCE, &delta), Ok(TARGET.to_vec()));
+
+        // An application header and the checksum that xdelta3 writes
+        let mut delta = MAGIC.to_vec();
+        delta.extend_from_slice(&[VCD_APPHEADER, 2, b'a', b'b']);
+        delta.extend_from_slice(&rfc_window(VCD_SOURCE | VCD_ADLER32, Some([0xa7, 0xfc, 0x0b, 0xbd])));
+        assert_eq!(decode(SOURCE, &delta), Ok(TARGET.to_vec()));
+
+        let mut delta = MAGIC.to_vec();
+        delta.push(0);
+        delta.extend_from_slice(&rfc_window(VCD_SOURCE | VCD_ADLER32, Some([0, 0, 0, 1])));
+        assert_eq!(
+            decode(SOURCE, &delta),
+            Err(VcdiffError::Checksum {
+                expected: 1,
+                actual: 0xa7fc_0bbd
+            })
+        );
+
+        // The second window copies from the target of the first one
+        let mut delta = MAGIC.to_vec();
+        delta.push(0);
+        delta.extend_from_slice(&rfc_window(VCD_SOURCE, None));
+        delta.extend_from_slice(&[VCD_TARGET, 4, 4, 7, 4, 0, 0, 1, 1, 20, 0]);
+        let mut target = TARGET.to_vec();
+        target.extend_from_slice(b"wxyz");
+        assert_eq!(decode(SOURCE, &delta), Ok(target));
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        assert_eq!(decode(SOURCE, b"VCD\0\0"), Err(VcdiffError::InvalidHeader));
+        assert_eq!(decode(SOURCE, &MAGIC), Err(VcdiffError::Truncated));
+        assert_eq!(
+            decode(SOURCE, &[0xd6, 0xc3, 0xc4, 0, VCD_DECOMPRESS, 2]),
+            Err(VcdiffError::Unsupported("secondary compression"))
+        );
+
+        let mut delta = MAGIC.to_vec();
+        delta.push(0);
+        delta.extend_from_slice(&rfc_window(VCD_SOURCE, None));
+        assert_eq!(decode(b"abc", &delta), Err(VcdiffError::InvalidSegment));
+        delta.pop();
+        assert_eq!(decode(SOURCE, &delta), Err(VcdiffError::Truncated));
+
+        // COPY 4 from the address 20, which is not decoded yet
+        let delta = [0xd6, 0xc3, 0xc4, 0, 0, VCD_SOURCE, 16, 0, 7, 4, 0, 0, 1, 1, 20, 20];
+        assert_eq!(decode(SOURCE, &delta), Err(VcdiffError::InvalidAddress));
+
+        // ADD 1 and COPY of the size usize::MAX, which overflows the target length
+        let mut instructions = vec![2, 19];
+        write_int(&mut instructions, usize::MAX);
+        let mut encoding = vec![1, 0, 1, instructions.len() as u8, 0, b'a'];
+        encoding.extend_from_slice(&instructions);
+        let mut delta = MAGIC.to_vec();
+        delta.extend_from_slice(&[0, 0, encoding.len() as u8]);
+        delta.extend_from_slice(&encoding);
+        assert_eq!(decode(SOURCE, &delta), Err(VcdiffError::Overflow));
+    }
+
+    #[test]
+    fn test_encode() {
+        let mut state = 7u32;
+        let source: Vec<u8> = (0..5000)
+            .map(|_| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                (state >> 16) as u8
+            })
+            .collect();
+        let mut target = source[2000..4000].to_vec();
+        target.extend_from_slice(&[0; 100]);
+        target.extend_from_slice(b"inserted");
+        target.extend_from_slice(&source[..2000]);
+        target.extend_from_slice(&source[1000..1500]);
+
+        let delta = encode(&source, &target);
+        assert!(delta.len() < 50);
+        assert_eq!(decode(&source, &delta), Ok(target));
+
+        assert_eq!(decode(SOURCE, &encode(SOURCE, TARGET)), Ok(TARGET.to_vec()));
+        assert_eq!(decode(b"", &encode(b"", b"zzzzzzzzab")), Ok(b"zzzzzzzzab".to_vec()));
+        assert_eq!(encode(SOURCE, b""), vec![0xd6, 0xc3, 0xc4, 0, 0]);
+        assert_eq!(decode(SOURCE, &encode(SOURCE, b"")), Ok(Vec::new()));
+    }
+
+    /// The fixtures of `tests/fixtures/vcdiff`, the deltas of which are written
+    /// by its `generate.sh` with xdelta3, or by its `assemble.py` in the same
+    /// layout on the hosts without xdelta3
+    fn fixtures() -> Vec<(PathBuf, Vec<u8>, Vec<u8>)> {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/vcdiff");
+        let mut fixtures = Vec::new();
+        for entry in fs::read_dir(&dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension() == Some(OsStr::new("target")) {
+                let source = fs::read(path.with_extension("source")).unwrap();
+                let target = fs::read(&path).unwrap();
+                fixtures.push((path.with_extension("vcdiff"), source, target));
+            }
+        }
+        fixtures.sort();
+        fixtures
+    }
+
+    /// Returns the indicators of the windows of the delta
+    /// and the address modes of its copies
+    fn layout(delta: &[u8]) -> (Vec<u8>, Vec<u8>) {
+        let table = code_table();
+        let mut reader = Reader::new(&delta[MAGIC.len()..]);
+        if reader.byte().unwrap() & VCD_APPHEADER != 0 {
+            let len = reader.int().unwrap();
+            reader.take(len).unwrap();
+        }
+
+        let (mut indicators, mut modes) = (Vec::new(), Vec::new());
+        while !reader.is_empty() {
+            let indicator = reader.byte().unwrap();
+            indicators.push(indicator);
+            if indicator & (VCD_SOURCE | VCD_TARGET) != 0 {
+                reader.int().unwrap();
+                reader.int().unwrap();
+            }
+            let len = reader.int().unwrap();
+            let mut window = Reader::new(reader.take(len).unwrap());
+            window.int().unwrap();
+            window.byte().unwrap();
+            let data_len = window.int().unwrap();
+            let instructions_len = window.int().unwrap();
+            window.int().unwrap();
+            if indicator & VCD_ADLER32 != 0 {
+                window.take(4).unwrap();
+            }
+            window.take(data_len).unwrap();
+
+            let mut instructions = Reader::new(window.take(instructions_len).unwrap());
+            while !instructions.is_empty() {
+                let (first, second) = table[instructions.byte().unwrap() as usize];
+                for inst in [first, second] {
+                    if inst.kind != InstKind::Noop && inst.size == 0 {
+                        instructions.int().unwrap();
+                    }
+                    if inst.kind == InstKind::Copy {
+                        modes.push(inst.mode);
+                    }
+                }
+            }
+        }
+        (indicators, modes)
+    }
+
+    #[test]
+    fn test_decode_fixtures() {
+        let (mut indicators, mut modes) = (Vec::new(), Vec::new());
+        for (delta_path, source, target) in fixtures() {
+            let delta = fs::read(&delta_path)
+                .unwrap_or_else(|error| panic!("{}: {}, run generate.sh", delta_path.display(), error));
+            assert_eq!(decode(&source, &delta), Ok(target), "{}", delta_path.display());
+
+            let layout = layout(&delta);
+            indicators.extend(layout.0);
+            modes.extend(layout.1);
+        }
+
+        // The checksums and the cached addresses that xdelta3 writes are decoded
+        assert!(indicators.contains(&(VCD_SOURCE | VCD_ADLER32)));
+        let near_modes = VCD_HERE + 1..VCD_HERE + 1 + NEAR_LEN as u8;
+        assert!(modes.iter().any(|mode| near_modes.contains(mode)));
+        assert!(modes.iter().any(|mode| *mode >= near_modes.end));
+    }
+
+    /// Runs xdelta3 in both directions if it is installed
+    #[test]
+    fn test_xdelta3_interop() {
+        if Command::new("xdelta3").arg("-V").output().is_err() {
+            return;
+        }
+        let dir = env::temp_dir().join(format!("ng-diff-vcdiff-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let (source_path, target_path) = (dir.join("source"), dir.join("target"));
+        let (delta_path, output_path) = (dir.join("delta"), dir.join("output"));
+        let xdelta3 = |args: &[&str], input: &Path, output: &Path| {
+            let status = Command::new("xdelta3")
+                .args(args)
+                .arg("-f")
+                .arg("-s")
+                .arg(&source_path)
+                .arg(input)
+                .arg(output)
+                .status()
+                .unwrap();
+            assert!(status.success());
+        };
+
+        for (_, source, target) in fixtures() {
+            fs::write(&source_path, &source).unwrap();
+            fs::write(&target_path, &target).unwrap();
+
+            fs::write(&delta_path, encode(&source, &target)).unwrap();
+            xdelta3(&["-d"], &delta_path, &output_path);
+            assert_eq!(fs::read(&output_path).unwrap(), target);
+
+            xdelta3(&["-e", "-S", "none"], &target_path, &delta_path);
+            assert_eq!(decode(&source, &fs::read(&delta_path).unwrap()), Ok(target));
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_ints() {
+        let mut bytes = Vec::new();
+        write_int(&mut bytes, 123_456_789);
+        // The example of RFC 3284, section 2
+        assert_eq!(bytes, [0xba, 0xef, 0x9a, 0x15]);
+        assert_eq!(Reader::new(&bytes).int(), Ok(123_456_789));
+        assert_eq!(int_len(123_456_789), 4);
+        assert_eq!(Reader::new(&[0xff; 11]).int(), Err(VcdiffError::Overflow));
+    }
+}
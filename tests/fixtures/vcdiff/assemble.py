@@ -0,0 +1,171 @@
+#!/usr/bin/env python3
+"""Assembles every <name>.vcdiff from <name>.source and <name>.target in the
+layout that `xdelta3 -e -S none` writes, for the hosts without xdelta3:
+the application header "<name>.target//<name>.source/", one window with
+VCD_SOURCE and VCD_ADLER32, the pairs of the default code table and the
+addresses encoded in the mode that xdelta3 picks, the near and same modes
+included. The bytes differ from those of xdelta3, whose matcher differs,
+and generate.sh overwrites them with its output where it is installed.
+"""
+
+import glob
+import os
+import zlib
+
+RUN, ADD, COPY = "run", "add", "copy"
+NEAR_LEN, SAME_LEN = 4, 3
+BLOCK_LEN = 6
+
+VCD_APPHEADER = 0x04
+VCD_SOURCE, VCD_ADLER32 = 0x01, 0x04
+
+
+def code_table():
+    """The default code table of RFC 3284, section 5.6"""
+    table = [((RUN, 0, 0), None)]
+    table += [((ADD, size, 0), None) for size in range(18)]
+    for mode in range(9):
+        table += [((COPY, size, mode), None) for size in [0] + list(range(4, 19))]
+    for mode in range(6):
+        for add in range(1, 5):
+            table += [((ADD, add, 0), (COPY, copy, mode)) for copy in range(4, 7)]
+    for mode in range(6, 9):
+        table += [((ADD, add, 0), (COPY, 4, mode)) for add in range(1, 5)]
+    table += [((COPY, 4, mode), (ADD, 1, 0)) for mode in range(9)]
+    assert len(table) == 256
+    return {pair: index for index, pair in enumerate(table)}
+
+
+def write_int(value):
+    groups = [value & 0x7F]
+    value >>= 7
+    while value:
+        groups.append(value & 0x7F | 0x80)
+        value >>= 7
+    return bytes(reversed(groups))
+
+
+class AddressCache:
+    def __init__(self):
+        self.near = [0] * NEAR_LEN
+        self.next_slot = 0
+        self.same = [0] * (SAME_LEN * 256)
+
+    def encode(self, address, here):
+        """Returns the mode and the encoded address like xd3_encode_address"""
+        best, mode = address, 0
+        if best >= 128 and here - address < best:
+            best, mode = here - address, 1
+        for slot, near in enumerate(self.near):
+            if best < 128:
+                break
+            if address >= near and address - near < best:
+                best, mode = address - near, slot + 2
+        if best < 128 or self.same[address % len(self.same)] != address:
+            encoded = write_int(best)
+        else:
+            index = address % len(self.same)
+            mode, encoded = NEAR_LEN + 2 + index // 256, bytes([index % 256])
+        self.near[self.next_slot] = address
+        self.next_slot = (self.next_slot + 1) % NEAR_LEN
+        self.same[address % len(self.same)] = address
+        return mode, encoded
+
+
+def match(source, target):
+    """Splits the target into the greedy longest copies of the source and the adds"""
+    index = {}
+    for offset in range(len(source) - BLOCK_LEN + 1):
+        index.setdefault(source[offset : offset + BLOCK_LEN], []).append(offset)
+
+    insts, i = [], 0
+    while i < len(target):
+        best = None
+        for offset in index.get(target[i : i + BLOCK_LEN], []):
+            size = 0
+            while offset + size < len(source) and i + size < len(target) and source[offset + size] == target[i + size]:
+                size += 1
+            if best is None or size > best[1]:
+                best = (offset, size)
+        if best is None:
+            if insts and insts[-1][0] == ADD:
+                insts[-1] = (ADD, insts[-1][1] + target[i : i + 1])
+            else:
+                insts.append((ADD, target[i : i + 1]))
+            i += 1
+        else:
+            insts.append((COPY, best))
+            i += best[1]
+    return insts
+
+
+def encode_window(source, target):
+    table = code_table()
+    cache = AddressCache()
+    data, instructions, addresses = bytearray(), bytearray(), bytearray()
+
+    # The instructions with their sizes and modes, whose addresses are encoded in order
+    coded, here = [], len(source)
+    for kind, value in match(source, target):
+        if kind == ADD:
+            data += value
+            coded.append(((ADD, len(value), 0), b""))
+            here += len(value)
+        else:
+            offset, size = value
+            mode, encoded = cache.encode(offset, here)
+            coded.append(((COPY, size, mode), encoded))
+            here += size
+
+    def single(inst):
+        kind, size, mode = inst
+        if (inst, None) in table:
+            return table[(inst, None)], b""
+        return table[((kind, 0, mode), None)], write_int(size)
+
+    position = 0
+    while position < len(coded):
+        inst, address = coded[position]
+        if position + 1 < len(coded) and (inst, coded[position + 1][0]) in table:
+            next_inst, next_address = coded[position + 1]
+            instructions.append(table[(inst, next_inst)])
+            addresses.extend(address + next_address)
+            position += 2
+        else:
+            opcode, size = single(inst)
+            instructions.append(opcode)
+            instructions.extend(size)
+            addresses.extend(address)
+            position += 1
+
+    encoding = write_int(len(target)) + bytes([0])
+    encoding += write_int(len(data)) + write_int(len(instructions)) + write_int(len(addresses))
+    encoding += zlib.adler32(target).to_bytes(4, "big")
+    encoding += bytes(data) + bytes(instructions) + bytes(addresses)
+
+    if source:
+        window = bytes([VCD_SOURCE | VCD_ADLER32]) + write_int(len(source)) + write_int(0)
+    else:
+        window = bytes([VCD_ADLER32])
+    return window + write_int(len(encoding)) + encoding
+
+
+def main():
+    os.chdir(os.path.dirname(os.path.abspath(__file__)))
+    for target_path in sorted(glob.glob("*.target")):
+        name = target_path[: -len(".target")]
+        with open(name + ".source", "rb") as file:
+            source = file.read()
+        with open(target_path, "rb") as file:
+            target = file.read()
+
+        app_header = "{0}.target//{0}.source/".format(name).encode()
+        delta = bytes([0xD6, 0xC3, 0xC4, 0x00, VCD_APPHEADER]) + write_int(len(app_header)) + app_header
+        if target:
+            delta += encode_window(source, target)
+        with open(name + ".vcdiff", "wb") as file:
+            file.write(delta)
+
+
+if __name__ == "__main__":
+    main()
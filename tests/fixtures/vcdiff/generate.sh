@@ -0,0 +1,12 @@
+#!/bin/sh
+# Encodes every <name>.target against its <name>.source by xdelta3 into
+# <name>.vcdiff, with the Adler-32 checksums but without the secondary
+# compression, which the decoder of ng_diff::vcdiff does not support.
+# The tests decode these deltas. On the hosts without xdelta3 assemble.py
+# writes them in the same layout instead.
+set -e
+cd "$(dirname "$0")"
+for target in *.target; do
+    name="${target%.target}"
+    xdelta3 -e -f -S none -s "$name.source" "$target" "$name.vcdiff"
+done